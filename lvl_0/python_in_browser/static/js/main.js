@@ -96,6 +96,62 @@ function getParameterValue(name) {
 }
 
 
+// Название и описание уровня, которые выставляет игра
+function updateLevelDescription() {
+    const urlParams = new URLSearchParams(window.location.search);
+    const title = urlParams.get('level_title');
+    const description = urlParams.get('level_description');
+    if (title !== null) {
+        document.getElementById("level_title").innerText = title;
+    }
+    if (description !== null && description !== "") {
+        document.getElementById("level_description").innerText = description;
+    }
+}
+setInterval(updateLevelDescription, 1000);
+
+
+// Статистика попытки, которую выставляет игра в параметре `stats` (JSON)
+function updateRunStats() {
+    const urlParams = new URLSearchParams(window.location.search);
+    const stats = urlParams.get('stats');
+    if (stats === null || stats === "") {
+        return;
+    }
+    const results = {"success": "Уровень пройден\n", "failure": "Уровень провален\n"};
+    try {
+        const s = JSON.parse(stats);
+        document.getElementById("run_stats").innerText =
+            (results[urlParams.get('result')] || "")
+            + `Очки: ${s.score}\n`
+            + `Выстрелы: ${s.shots}, точность: ${Math.round(s.accuracy * 100)}%\n`
+            + `Попадания в мишень: ${s.target_hits}\n`
+            + `Попадания во врагов: ${s.enemy_hits}, уничтожено: ${s.enemy_kills}\n`
+            + `Получено урона: ${s.damage_taken}\n`
+            + `Время: ${s.time.toFixed(1)} с`
+            + (s.clear_time !== null ? `, пройдено за ${s.clear_time.toFixed(1)} с` : "");
+    } catch (err) {
+        console.log(err);
+    }
+}
+setInterval(updateRunStats, 1000);
+
+
+// Скачать запись прогона, которую игра копит при `?record=1` в параметре `replay_record`
+function downloadReplay() {
+    const record = new URLSearchParams(window.location.search).get('replay_record');
+    if (record === null || record === "") {
+        addToOutput("Записи нет: откройте страницу с параметром ?record=1\n");
+        return;
+    }
+    const link = document.createElement("a");
+    link.href = URL.createObjectURL(new Blob([record], {type: "text/plain"}));
+    link.download = "run.replay";
+    link.click();
+    URL.revokeObjectURL(link.href);
+}
+
+
 function sleep(ms) {
     return new Promise(resolve => setTimeout(resolve, ms));
 }
@@ -1,37 +1,71 @@
 //! ассеты с предворительной загрузкой
+//!
+//! Файлы перечислены в `assets/manifest.json` по именам и читаются одновременно.
+//! Вместо текстуры, которую не удалось прочитать, берется заглушка, звук без файла
+//! не проигрывается, а ошибка попадает в `Assets::errors`.
 
 use macroquad::audio::{load_sound, Sound};
-use macroquad::prelude::{info};
-use macroquad::texture::{load_texture, Texture2D};
+use macroquad::experimental::coroutines::{start_coroutine, Coroutine};
+use macroquad::file::load_string;
+use macroquad::prelude::info;
+use macroquad::texture::{load_texture, FilterMode, Image, Texture2D};
 use macroquad::Error;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+pub(crate) const ASSETS_DIR: &str = "../assets/";
+const MANIFEST_PATH: &str = "../assets/manifest.json";
+
+/// Список файлов ассетов: имя -> путь относительно `assets/`.
+#[derive(Debug, Default, Deserialize)]
+struct Manifest {
+    #[serde(default)]
+    textures: BTreeMap<String, String>,
+    #[serde(default)]
+    sounds: BTreeMap<String, String>,
+}
 
 /// Набор ассетов. Текстуры и звуки.
 pub(crate) struct Assets {
     pub(crate) main_unit_texture: Texture2D,
-    pub(crate) main_unit_shoot_sound: Sound,
-    pub(crate) target_impact_sound: Sound,
+    pub(crate) main_unit_shoot_sound: Option<Sound>, // `None` - файл не прочитался
+    pub(crate) target_impact_sound: Option<Sound>,
     pub(crate) target_unit_texture: Texture2D,
     pub(crate) target_unit_shadow_texture: Texture2D,
     pub(crate) projectile_texture: Texture2D,
     pub(crate) enemy_unit_gray: Texture2D,
+    pub(crate) wall_thin_texture: Texture2D,
+    pub(crate) wall_thick_texture: Texture2D,
+    pub(crate) wall_impact_sound: Option<Sound>,
+    pub(crate) music: Option<Sound>, // музыка необязательна
+    /// что не удалось загрузить, для экрана
+    pub(crate) errors: Vec<String>,
+    #[cfg(not(target_arch = "wasm32"))]
+    manifest: Manifest,
+    #[cfg(not(target_arch = "wasm32"))]
+    reloading: Vec<Pending<Sound>>, // звуки, которые перечитываются после правки
 }
 
 impl Assets {
-    /// Читать файлы ассетов
-    pub async fn new() -> Result<Self, Error> {
-        info!("WASM LOG: Начало загрузки текстур");
-        let main_unit_texture: Texture2D = load_texture("../assets/pointer/pointer_3.png").await?;
-        let main_unit_shoot_sound: Sound = load_sound("../assets/sound/4 XSA_Weapon.wav").await?;
-        let target_impact_sound: Sound =
-            load_sound("../assets/sound/hit-with-something.wav").await?;
-        let target_unit_texture = load_texture("../assets/pointer/target_unit_3_2.png").await?;
-        let target_unit_shadow_texture =
-            load_texture("../assets/pointer/target_unit_3_shadow.png").await?;
-        let enemy_unit_gray = load_texture("../assets/pointer/enemy_unit_gray.png").await?;
-        let projectile_texture = load_texture("../assets/pointer/projectile_glow_7.png").await?;
-        info!("WASM LOG: Текстуры загружены");
+    /// Собрать ассеты из загруженных файлов, недостающие текстуры заменить заглушками.
+    fn new(mut loader: AssetsLoader) -> Self {
+        let main_unit_texture = loader.texture("main_unit");
+        let main_unit_shoot_sound = loader.sound("main_unit_shoot");
+        let target_impact_sound = loader.sound("impact");
+        let target_unit_texture = loader.texture("target_unit");
+        let target_unit_shadow_texture = loader.texture("target_unit_shadow");
+        let enemy_unit_gray = loader.texture("enemy_unit");
+        let projectile_texture = loader.texture("projectile");
+        let wall_thin_texture = loader.texture("wall_thin");
+        let wall_thick_texture = loader.texture("wall_thick");
+        let wall_impact_sound = loader.sound("wall_impact");
+        let music = if loader.manifest.sounds.contains_key("music") {
+            loader.sound("music")
+        } else {
+            None
+        };
 
-        Ok(Self {
+        Self {
             main_unit_texture,
             main_unit_shoot_sound,
             target_impact_sound,
@@ -39,6 +73,315 @@ impl Assets {
             target_unit_shadow_texture,
             enemy_unit_gray,
             projectile_texture,
-        })
+            wall_thin_texture,
+            wall_thick_texture,
+            wall_impact_sound,
+            music,
+            errors: loader.errors,
+            #[cfg(not(target_arch = "wasm32"))]
+            manifest: loader.manifest,
+            #[cfg(not(target_arch = "wasm32"))]
+            reloading: vec![],
+        }
+    }
+}
+
+/// Перезагрузка файлов во время игры, только нативно.
+#[cfg(not(target_arch = "wasm32"))]
+impl Assets {
+    /// Перечитать файл для всех имен `manifest.json`, которые из него читаются.
+    /// Текстуры заменяются сразу, звуки - в `update` после загрузки.
+    ///
+    /// ### Аргументы
+    ///
+    /// * `path` - путь файла относительно `assets/`, как в `manifest.json`.
+    pub fn reload(&mut self, path: &str) {
+        let file = format!("{}{}", ASSETS_DIR, path);
+        let textures: Vec<String> = names(&self.manifest.textures, path);
+        for name in textures {
+            let image = std::fs::read(&file)
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| {
+                    Image::from_file_with_format(&bytes, None).map_err(|e| e.to_string())
+                });
+            match image {
+                Ok(image) => {
+                    info!("assets: {} перечитан", path);
+                    self.errors
+                        .retain(|e| !e.starts_with(&format!("{}: ", name)));
+                    self.set_texture(&name, Texture2D::from_image(&image));
+                }
+                Err(e) => error(&mut self.errors, &name, &format!("{}: {}", path, e)),
+            }
+        }
+        for name in names(&self.manifest.sounds, path) {
+            let file = file.clone();
+            self.reloading.push(Pending {
+                name,
+                path: path.to_string(),
+                coroutine: start_coroutine(async move { load_sound(&file).await }),
+            });
+        }
+    }
+
+    /// Перечитать `manifest.json` и файлы, пути которых в нем изменились.
+    pub fn reload_manifest(&mut self) {
+        let manifest = match std::fs::read_to_string(MANIFEST_PATH)
+            .map_err(|e| e.to_string())
+            .and_then(|text| serde_json::from_str::<Manifest>(&text).map_err(|e| e.to_string()))
+        {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                error(&mut self.errors, "manifest.json", &e);
+                return;
+            }
+        };
+        self.errors.retain(|e| !e.starts_with("manifest.json: "));
+        let changed: Vec<String> = manifest
+            .textures
+            .iter()
+            .filter(|(name, path)| self.manifest.textures.get(*name) != Some(*path))
+            .chain(
+                manifest
+                    .sounds
+                    .iter()
+                    .filter(|(name, path)| self.manifest.sounds.get(*name) != Some(*path)),
+            )
+            .map(|(_, path)| path.clone())
+            .collect();
+        self.manifest = manifest;
+        for path in changed {
+            self.reload(&path);
+        }
+    }
+
+    /// Забрать перечитанные звуки.
+    pub fn update(&mut self) {
+        for (name, sound) in finish(&mut self.reloading, &mut self.errors) {
+            info!("assets: {} перечитан", name);
+            self.errors
+                .retain(|e| !e.starts_with(&format!("{}: ", name)));
+            self.set_sound(&name, sound);
+        }
+    }
+
+    /// Заменить текстуру `name`, неизвестное имя пропускается
+    fn set_texture(&mut self, name: &str, texture: Texture2D) {
+        let field = match name {
+            "main_unit" => &mut self.main_unit_texture,
+            "target_unit" => &mut self.target_unit_texture,
+            "target_unit_shadow" => &mut self.target_unit_shadow_texture,
+            "enemy_unit" => &mut self.enemy_unit_gray,
+            "projectile" => &mut self.projectile_texture,
+            "wall_thin" => &mut self.wall_thin_texture,
+            "wall_thick" => &mut self.wall_thick_texture,
+            _ => return,
+        };
+        *field = texture;
+    }
+
+    /// Заменить звук `name`, неизвестное имя пропускается
+    fn set_sound(&mut self, name: &str, sound: Sound) {
+        let field = match name {
+            "main_unit_shoot" => &mut self.main_unit_shoot_sound,
+            "impact" => &mut self.target_impact_sound,
+            "wall_impact" => &mut self.wall_impact_sound,
+            "music" => &mut self.music,
+            _ => return,
+        };
+        *field = Some(sound);
+    }
+}
+
+/// Имена, которые читаются из файла `path`
+#[cfg(not(target_arch = "wasm32"))]
+fn names(files: &BTreeMap<String, String>, path: &str) -> Vec<String> {
+    files
+        .iter()
+        .filter(|(_, file)| file.as_str() == path)
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Файл, который еще читается.
+struct Pending<T: 'static> {
+    name: String,
+    path: String,
+    coroutine: Coroutine<Result<T, Error>>,
+}
+
+/// Загрузка по списку `manifest.json`.
+/// Все файлы читаются одновременно в корутинах, между кадрами можно
+/// показывать прогресс.
+pub(crate) struct AssetsLoader {
+    manifest: Manifest,
+    errors: Vec<String>,
+    total: usize,
+    pending_textures: Vec<Pending<Texture2D>>,
+    pending_sounds: Vec<Pending<Sound>>,
+    textures: BTreeMap<String, Texture2D>,
+    sounds: BTreeMap<String, Sound>,
+}
+
+impl AssetsLoader {
+    /// Прочитать `manifest.json` и начать загрузку всех файлов.
+    pub async fn start() -> Self {
+        info!("WASM LOG: Начало загрузки текстур");
+        let mut errors = vec![];
+        let manifest = match load_string(MANIFEST_PATH).await {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+                errors.push(format!("manifest.json: {}", e));
+                Manifest::default()
+            }),
+            Err(e) => {
+                errors.push(format!("manifest.json: {}", e));
+                Manifest::default()
+            }
+        };
+
+        let pending_textures: Vec<_> = manifest
+            .textures
+            .iter()
+            .map(|(name, path)| {
+                let file = format!("{}{}", ASSETS_DIR, path);
+                Pending {
+                    name: name.clone(),
+                    path: path.clone(),
+                    coroutine: start_coroutine(async move { load_texture(&file).await }),
+                }
+            })
+            .collect();
+        let pending_sounds: Vec<_> = manifest
+            .sounds
+            .iter()
+            .map(|(name, path)| {
+                let file = format!("{}{}", ASSETS_DIR, path);
+                Pending {
+                    name: name.clone(),
+                    path: path.clone(),
+                    coroutine: start_coroutine(async move { load_sound(&file).await }),
+                }
+            })
+            .collect();
+
+        Self {
+            total: pending_textures.len() + pending_sounds.len(),
+            manifest,
+            errors,
+            pending_textures,
+            pending_sounds,
+            textures: BTreeMap::new(),
+            sounds: BTreeMap::new(),
+        }
+    }
+
+    /// Забрать загруженные файлы. Вызывается каждый кадр, пока идет загрузка.
+    pub fn update(&mut self) {
+        let textures = finish(&mut self.pending_textures, &mut self.errors);
+        self.textures.extend(textures);
+        let sounds = finish(&mut self.pending_sounds, &mut self.errors);
+        self.sounds.extend(sounds);
+    }
+
+    /// Все файлы прочитаны или не прочитались.
+    pub fn is_done(&self) -> bool {
+        self.pending_textures.is_empty() && self.pending_sounds.is_empty()
+    }
+
+    /// Доля прочитанных файлов от 0 до 1.
+    pub fn progress(&self) -> f32 {
+        if self.total == 0 {
+            return 1.;
+        }
+        let pending = self.pending_textures.len() + self.pending_sounds.len();
+        (self.total - pending) as f32 / self.total as f32
+    }
+
+    /// Пути файлов, которые еще читаются.
+    pub fn pending(&self) -> Vec<&str> {
+        let textures = self.pending_textures.iter().map(|p| p.path.as_str());
+        let sounds = self.pending_sounds.iter().map(|p| p.path.as_str());
+        textures.chain(sounds).collect()
+    }
+
+    /// Закончить загрузку.
+    pub async fn finish(self) -> Assets {
+        let assets = Assets::new(self);
+        info!("WASM LOG: Текстуры загружены");
+        assets
+    }
+
+    /// Текстура `name` или заглушка
+    fn texture(&mut self, name: &str) -> Texture2D {
+        match self.textures.get(name) {
+            Some(texture) => texture.clone(),
+            None => {
+                self.missing(name);
+                placeholder_texture()
+            }
+        }
+    }
+
+    /// Звук `name`, `None` - звука нет, ошибка уже записана
+    fn sound(&mut self, name: &str) -> Option<Sound> {
+        let sound = self.sounds.get(name).cloned();
+        if sound.is_none() {
+            self.missing(name);
+        }
+        sound
+    }
+
+    /// Имени нет в списке. Ошибки чтения файлов уже записаны.
+    fn missing(&mut self, name: &str) {
+        if !self.manifest.textures.contains_key(name) && !self.manifest.sounds.contains_key(name) {
+            error(&mut self.errors, name, "нет в manifest.json");
+        }
+    }
+}
+
+/// Забрать прочитанные файлы из `pending`, ошибки записать в `errors`
+fn finish<T: 'static>(pending: &mut Vec<Pending<T>>, errors: &mut Vec<String>) -> Vec<(String, T)> {
+    let mut loaded = vec![];
+    pending.retain(|file| {
+        if !file.coroutine.is_done() {
+            return true;
+        }
+        match file.coroutine.retrieve() {
+            Some(Ok(value)) => loaded.push((file.name.clone(), value)),
+            Some(Err(e)) => error(errors, &file.name, &format!("{}: {}", file.path, e)),
+            None => error(
+                errors,
+                &file.name,
+                &format!("{}: загрузка прервана", file.path),
+            ),
+        }
+        false
+    });
+    loaded
+}
+
+fn error(errors: &mut Vec<String>, name: &str, error: &str) {
+    info!("assets: {}: {}", name, error);
+    errors.push(format!("{}: {}", name, error));
+}
+
+/// Заглушка текстуры: крупная клетка, заметная на поле.
+/// Юниты рисуются в размере текстуры, поэтому заглушка размером с юнита.
+fn placeholder_texture() -> Texture2D {
+    const SIZE: u16 = 64;
+    const CELL: u16 = 16;
+    let mut pixels = Vec::with_capacity(SIZE as usize * SIZE as usize * 4);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let color = if (x / CELL + y / CELL).is_multiple_of(2) {
+                [255, 0, 255, 255]
+            } else {
+                [0, 0, 0, 255]
+            };
+            pixels.extend_from_slice(&color);
+        }
     }
+    let texture = Texture2D::from_rgba8(SIZE, SIZE, &pixels);
+    texture.set_filter(FilterMode::Nearest);
+    texture
 }
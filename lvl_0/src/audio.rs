@@ -0,0 +1,193 @@
+//! звук: общая громкость, громкость эффектов и музыки, выключение звука
+//! и ограничение одновременно звучащих одинаковых эффектов
+
+use crate::config::config;
+use macroquad::audio::{self, PlaySoundParams, Sound};
+use std::collections::HashMap;
+
+/// Звуковой эффект. У каждого свой лимит одновременных проигрываний,
+/// даже если файл звука общий.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Sfx {
+    MainUnitShoot,
+    MainUnitImpact,
+    TargetUnitImpact,
+    EnemyUnitShoot,
+    EnemyUnitImpact,
+    WallImpact,
+}
+
+/// Проигрывание звуков игры.
+pub struct Audio {
+    muted: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    mute_setting: bool, // `mute` из настроек, M переключает `muted` поверх него
+    limiter: Limiter,
+    music: Option<Sound>,
+}
+
+impl Audio {
+    pub fn new() -> Self {
+        Self {
+            muted: config().mute,
+            #[cfg(not(target_arch = "wasm32"))]
+            mute_setting: config().mute,
+            limiter: Limiter::default(),
+            music: None,
+        }
+    }
+
+    /// Отсчитать время кадра `dt` для лимита эффектов.
+    pub fn update(&mut self, dt: f32) {
+        self.limiter.time += dt;
+    }
+
+    /// Проиграть эффект один раз.
+    /// Если `sound_max_instances` копий эффекта еще звучат, новое проигрывание пропускается.
+    ///
+    /// ### Аргументы
+    ///
+    /// * `sfx` - эффект, по нему считается лимит.
+    /// * `sound` - звук, `None` - файл не прочитался, эффект пропускается.
+    /// * `volume` - громкость эффекта от 0 до 1, без учета общей громкости.
+    pub fn play(&mut self, sfx: Sfx, sound: Option<&Sound>, volume: f32) {
+        let Some(sound) = sound else {
+            return;
+        };
+        if self.muted {
+            return;
+        }
+        if !self.limiter.start(
+            sfx,
+            config().sound_max_instances,
+            config().sound_instance_time,
+        ) {
+            return;
+        }
+        audio::play_sound(
+            sound,
+            PlaySoundParams {
+                looped: false,
+                volume: volume * config().sfx_volume * config().master_volume,
+            },
+        );
+    }
+
+    /// Включить музыку по кругу вместо прошлой.
+    pub fn play_music(&mut self, sound: &Sound) {
+        if let Some(music) = &self.music {
+            audio::stop_sound(music);
+        }
+        audio::play_sound(
+            sound,
+            PlaySoundParams {
+                looped: true,
+                volume: self.music_volume(),
+            },
+        );
+        self.music = Some(sound.clone());
+    }
+
+    /// Выключить или включить звук.
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+        self.update_music_volume();
+    }
+
+    /// Применить перечитанные настройки: `mute`, если он изменился, и громкость музыки.
+    /// Громкость эффектов берется из настроек при каждом проигрывании.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn apply_config(&mut self) {
+        if config().mute != self.mute_setting {
+            self.mute_setting = config().mute;
+            self.muted = self.mute_setting;
+        }
+        self.update_music_volume();
+    }
+
+    fn update_music_volume(&self) {
+        if let Some(music) = &self.music {
+            audio::set_sound_volume(music, self.music_volume());
+        }
+    }
+
+    fn music_volume(&self) -> f32 {
+        if self.muted {
+            0.
+        } else {
+            config().music_volume * config().master_volume
+        }
+    }
+}
+
+/// Лимит одновременно звучащих копий эффекта.
+/// Копия считается звучащей `instance_time` секунд после начала - примерно длину звука.
+#[derive(Default)]
+struct Limiter {
+    time: f32,
+    started: HashMap<Sfx, Vec<f32>>, // время начала еще звучащих копий эффекта
+}
+
+impl Limiter {
+    /// Начать копию эффекта `sfx`, если звучит меньше `max_instances` копий.
+    ///
+    /// ### Возвращаемое значение
+    ///
+    /// `false`, если эффект нужно пропустить.
+    fn start(&mut self, sfx: Sfx, max_instances: usize, instance_time: f32) -> bool {
+        let time = self.time;
+        let started = self.started.entry(sfx).or_default();
+        started.retain(|start| time - start < instance_time);
+        if started.len() >= max_instances {
+            return false;
+        }
+        started.push(time);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{MAIN_UNIT_SHOOT_DELAY, SOUND_INSTANCE_TIME, SOUND_MAX_INSTANCES};
+
+    /// Сколько выстрелов прозвучит за `duration` секунд при стрельбе каждые `delay`.
+    fn played(limiter: &mut Limiter, delay: f32, duration: f32) -> usize {
+        let mut played = 0;
+        while limiter.time < duration {
+            if limiter.start(Sfx::MainUnitShoot, SOUND_MAX_INSTANCES, SOUND_INSTANCE_TIME) {
+                played += 1;
+            }
+            limiter.time += delay;
+        }
+        played
+    }
+
+    #[test]
+    fn continuous_fire_is_limited() {
+        let mut limiter = Limiter::default();
+        let shots = (2. / MAIN_UNIT_SHOOT_DELAY).round() as usize;
+        let played = played(&mut limiter, MAIN_UNIT_SHOOT_DELAY, 2.);
+        assert!(played < shots / 2, "{} из {}", played, shots);
+        // звучащих копий не больше лимита
+        assert!(limiter.started[&Sfx::MainUnitShoot].len() <= SOUND_MAX_INSTANCES);
+    }
+
+    #[test]
+    fn rare_shots_are_not_limited() {
+        let mut limiter = Limiter::default();
+        assert_eq!(played(&mut limiter, SOUND_INSTANCE_TIME, 5.), 5);
+    }
+
+    #[test]
+    fn effects_are_limited_separately() {
+        let mut limiter = Limiter::default();
+        for _ in 0..SOUND_MAX_INSTANCES {
+            assert!(limiter.start(Sfx::MainUnitShoot, SOUND_MAX_INSTANCES, 1.));
+        }
+        assert!(!limiter.start(Sfx::MainUnitShoot, SOUND_MAX_INSTANCES, 1.));
+        assert!(limiter.start(Sfx::WallImpact, SOUND_MAX_INSTANCES, 1.));
+        limiter.time = 1.;
+        assert!(limiter.start(Sfx::MainUnitShoot, SOUND_MAX_INSTANCES, 1.));
+    }
+}
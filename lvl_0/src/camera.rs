@@ -0,0 +1,43 @@
+//! камера: мир в постоянных координатах уровня при любом размере окна
+
+use macroquad::prelude::{screen_height, screen_width, vec2, Camera2D, Vec2};
+
+/// Камера, которая показывает прямоугольник мира целиком и сохраняет пропорции.
+/// Лишнее место окна остается полями по краям.
+///
+/// ### Аргументы
+///
+/// * `center` - центр показываемого прямоугольника в координатах мира.
+/// * `size` - размер показываемого прямоугольника.
+pub fn fit_camera(center: Vec2, size: Vec2) -> Camera2D {
+    let scale = f32::min(screen_width() / size.x, screen_height() / size.y);
+    let visible = vec2(screen_width(), screen_height()) / scale;
+    Camera2D {
+        target: center,
+        // ось y вниз, как в координатах окна
+        zoom: vec2(2. / visible.x, 2. / visible.y),
+        ..Default::default()
+    }
+}
+
+/// Центр камеры, которая следует за точкой `target` и не выходит за край поля.
+/// Если поле меньше видимой части, оно остается в центре.
+///
+/// ### Аргументы
+///
+/// * `target` - точка, за которой следует камера.
+/// * `view` - размер видимой части поля.
+/// * `size` - размер поля.
+pub fn follow_center(target: Vec2, view: Vec2, size: Vec2) -> Vec2 {
+    let axis = |target: f32, view: f32, size: f32| {
+        if view >= size {
+            size * 0.5
+        } else {
+            target.clamp(view * 0.5, size - view * 0.5)
+        }
+    };
+    vec2(
+        axis(target.x, view.x, size.x),
+        axis(target.y, view.y, size.y),
+    )
+}
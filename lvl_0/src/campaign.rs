@@ -0,0 +1,135 @@
+//! кампания: уровни по порядку и открытие следующего после прохождения
+//!
+//! Порядок уровней задается файлом `assets/levels/campaign.json`:
+//! ```json
+//! { "levels": ["lvl_0", "lvl_1"] }
+//! ```
+
+use crate::level::Level;
+use macroquad::file::load_string;
+use macroquad::prelude::info;
+use serde::Deserialize;
+
+const CAMPAIGN_PATH: &str = "../assets/levels/campaign.json";
+
+#[derive(Deserialize)]
+struct CampaignFile {
+    levels: Vec<String>,
+}
+
+/// Уровни кампании и прогресс игрока.
+pub struct Campaign {
+    levels: Vec<Level>,
+    current: usize,
+    unlocked: usize, // сколько уровней открыто, не меньше одного
+    active: bool,    // `false`, если играется уровень вне кампании
+}
+
+impl Campaign {
+    /// Прочитать все уровни кампании.
+    /// Уровни, которые не удалось прочитать, пропускаются.
+    ///
+    /// ### Аргументы
+    ///
+    /// * `unlocked` - сколько уровней уже открыто.
+    pub async fn load(unlocked: usize) -> Self {
+        let names = match load_string(CAMPAIGN_PATH).await {
+            Ok(text) => match serde_json::from_str::<CampaignFile>(&text) {
+                Ok(file) => file.levels,
+                Err(e) => {
+                    info!("campaign: {}", e);
+                    vec![]
+                }
+            },
+            Err(e) => {
+                info!("campaign: не удалось прочитать {}: {}", CAMPAIGN_PATH, e);
+                vec![]
+            }
+        };
+
+        let mut levels = vec![];
+        for name in names.iter() {
+            match Level::load(name).await {
+                Ok(level) => levels.push(level),
+                Err(e) => info!("{}", e),
+            }
+        }
+        if levels.is_empty() {
+            levels.push(Level::fallback());
+        }
+
+        let unlocked = unlocked.clamp(1, levels.len());
+        Self {
+            levels,
+            current: unlocked - 1,
+            unlocked,
+            active: true,
+        }
+    }
+
+    /// Текущий уровень кампании.
+    pub fn current(&self) -> &Level {
+        &self.levels[self.current]
+    }
+
+    /// Сколько уровней открыто.
+    pub fn unlocked(&self) -> usize {
+        self.unlocked
+    }
+
+    /// Есть ли уровень `name` в кампании.
+    pub fn contains(&self, name: &str) -> bool {
+        self.levels.iter().any(|l| l.name == name)
+    }
+
+    /// Перейти на уровень `name`.
+    ///
+    /// ### Аргументы
+    ///
+    /// * `name` - имя уровня кампании.
+    /// * `unlock` - открыть уровень, если он еще закрыт.
+    ///
+    /// ### Возвращаемое значение
+    ///
+    /// `false`, если уровня нет в кампании или он закрыт.
+    pub fn select(&mut self, name: &str, unlock: bool) -> bool {
+        let Some(index) = self.levels.iter().position(|l| l.name == name) else {
+            return false;
+        };
+        if index >= self.unlocked {
+            if !unlock {
+                return false;
+            }
+            self.unlocked = index + 1;
+        }
+        self.current = index;
+        self.active = true;
+        true
+    }
+
+    /// Заменить уровень кампании с тем же именем, например после правки файла.
+    pub fn replace(&mut self, level: Level) {
+        if let Some(old) = self.levels.iter_mut().find(|l| l.name == level.name) {
+            *old = level;
+        }
+    }
+
+    /// Играется уровень вне кампании.
+    pub fn leave(&mut self) {
+        self.active = false;
+    }
+
+    /// Текущий уровень пройден: открыть следующий и перейти на него.
+    ///
+    /// ### Возвращаемое значение
+    ///
+    /// Следующий уровень или `None`, если он последний или уровень вне кампании.
+    pub fn complete_current(&mut self) -> Option<&Level> {
+        if !self.active || self.current + 1 >= self.levels.len() {
+            return None;
+        }
+        self.current += 1;
+        self.unlocked = self.unlocked.max(self.current + 1);
+        Some(&self.levels[self.current])
+    }
+}
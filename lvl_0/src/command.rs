@@ -1,9 +1,13 @@
 use macroquad::math::Vec2;
 
+/// Управление главным юнитом на один шаг симуляции.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Command {
     pub shoot: bool,
     pub wasd: Vec2, // смещение
     pub rotation: f32,
+    pub target_point: Vec2, // точка прицеливания
+    pub auto_aim: bool,     // наведение по `rotation`, а не по `target_point`
 }
 
 impl Command {
@@ -12,6 +16,37 @@ impl Command {
             shoot: false,
             wasd: Vec2::new(0., 0.),
             rotation: 0.,
+            target_point: Vec2::new(0., 0.),
+            auto_aim: false,
+        }
+    }
+}
+
+/// Команда страницы игре, URL аргумент `command`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HostCommand {
+    /// выстрел в `target_point_x`, `target_point_y`
+    Shoot,
+    /// начать игру из меню
+    Start,
+    Pause,
+    Resume,
+    /// начать уровень заново
+    Restart,
+    /// следующий уровень после итогов
+    Next,
+}
+
+impl HostCommand {
+    pub fn parse(command: &str) -> Option<Self> {
+        match command {
+            "Shoot" => Some(Self::Shoot),
+            "Start" => Some(Self::Start),
+            "Pause" => Some(Self::Pause),
+            "Resume" => Some(Self::Resume),
+            "Restart" => Some(Self::Restart),
+            "Next" => Some(Self::Next),
+            _ => None,
         }
     }
 }
@@ -0,0 +1,284 @@
+//! настройки игры во время выполнения
+//!
+//! Значения по умолчанию - константы из `settings.rs`. Нативно их переопределяет файл
+//! `assets/config.json` (другой путь - `--config=path`), затем аргументы `--<имя>=<значение>`;
+//! в браузере - URL параметры `?<имя>=<значение>`. Имена - поля `Config`, значения в JSON:
+//! `?enemy_unit_speed=200`, `?ground_color=[0.5,0.5,0.5,1]`.
+
+use crate::settings::*;
+use crate::utils::get_command_line_argument;
+use macroquad::prelude::{info, Color};
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+#[cfg(not(target_arch = "wasm32"))]
+const CONFIG_PATH: &str = "../assets/config.json";
+
+static CONFIG: RwLock<Config> = RwLock::new(Config::DEFAULT);
+
+/// Текущие настройки.
+pub fn config() -> Config {
+    *CONFIG.read().unwrap()
+}
+
+/// Заменить настройки.
+pub fn set_config(config: Config) {
+    *CONFIG.write().unwrap() = config;
+}
+
+/// Настраиваемые параметры игры.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    // цвета, `[r, g, b, a]` от 0 до 1
+    #[serde(with = "color")]
+    pub ground_color: Color,
+    #[serde(with = "color")]
+    pub unit_color: Color,
+    #[serde(with = "color")]
+    pub projectile_color: Color,
+    #[serde(with = "color")]
+    pub enemy_projectile_color: Color,
+    #[serde(with = "color")]
+    pub wall_color: Color,
+    #[serde(with = "color")]
+    pub debug_color: Color,
+    #[serde(with = "color")]
+    pub hud_color: Color,
+    #[serde(with = "color")]
+    pub health_bar_color: Color,
+    #[serde(with = "color")]
+    pub health_bar_back_color: Color,
+    #[serde(with = "color")]
+    pub screen_shade_color: Color,
+    #[serde(with = "color")]
+    pub screen_text_color: Color,
+    #[serde(with = "color")]
+    pub minimap_color: Color,
+    #[serde(with = "color")]
+    pub minimap_enemy_color: Color,
+    #[serde(with = "color")]
+    pub minimap_target_color: Color,
+    // громкость звуков от 0 до 1
+    pub master_volume: f32,
+    pub sfx_volume: f32,
+    pub music_volume: f32,
+    pub mute: bool,
+    pub sound_max_instances: usize,
+    pub sound_instance_time: f32,
+    pub main_unit_shoot_sound_volume: f32,
+    pub main_unit_impact_sound_volume: f32,
+    pub target_unit_impact_sound_volume: f32,
+    pub enemy_unit_impact_sound_volume: f32,
+    pub enemy_unit_shoot_sound_volume: f32,
+    pub wall_impact_sound_volume: f32,
+    // main_unit; скорость, перезарядка, дальность и здоровье - значения по умолчанию для уровней
+    pub main_unit_speed: f32,
+    pub main_unit_shoot_delay: f32,
+    pub main_unit_shoot_range: f32,
+    pub main_unit_hit_points: f32,
+    pub main_unit_radius: f32,
+    pub main_unit_invulnerability_time: f32,
+    // target_unit
+    pub target_unit_radius: f32,
+    pub target_unit_destroyed_hit_points: f32,
+    // enemy_unit; скорости и оружие - значения по умолчанию для уровней
+    pub enemy_unit_speed: f32,
+    pub enemy_unit_rotation_speed: f32,
+    pub enemy_unit_radius: f32,
+    pub enemy_unit_contact_damage: f32,
+    pub enemy_unit_shoot_delay: f32,
+    pub enemy_unit_shoot_range: f32,
+    pub enemy_unit_shoot_accuracy: f32,
+    pub enemy_unit_shoot_spread: f32,
+    pub enemy_unit_projectile_speed: f32,
+    pub enemy_unit_projectile_damage: f32,
+    pub enemy_unit_spawn_spread: f32,
+    pub enemy_unit_sight_range: f32,
+    pub enemy_unit_patrol_tolerance: f32,
+    pub enemy_unit_separation_radius: f32,
+    pub enemy_unit_separation_weight: f32,
+    pub enemy_unit_alignment_radius: f32,
+    pub enemy_unit_alignment_weight: f32,
+    pub enemy_unit_cohesion_radius: f32,
+    pub enemy_unit_cohesion_weight: f32,
+    // очки
+    pub score_target_hit: i32,
+    pub score_enemy_kill: i32,
+    pub score_damage_penalty: f32,
+    pub score_clear_bonus: i32,
+    // волны и поиск пути
+    pub wave_delay: f32,
+    pub navigation_cell_size: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl Config {
+    pub const DEFAULT: Self = Self {
+        // цвета, `[r, g, b, a]` от 0 до 1
+        ground_color: GROUND_COLOR,
+        unit_color: UNIT_COLOR,
+        projectile_color: PROJECTILE_COLOR,
+        enemy_projectile_color: ENEMY_PROJECTILE_COLOR,
+        wall_color: WALL_COLOR,
+        debug_color: DEBUG_COLOR,
+        hud_color: HUD_COLOR,
+        health_bar_color: HEALTH_BAR_COLOR,
+        health_bar_back_color: HEALTH_BAR_BACK_COLOR,
+        screen_shade_color: SCREEN_SHADE_COLOR,
+        screen_text_color: SCREEN_TEXT_COLOR,
+        minimap_color: MINIMAP_COLOR,
+        minimap_enemy_color: MINIMAP_ENEMY_COLOR,
+        minimap_target_color: MINIMAP_TARGET_COLOR,
+        // громкость звуков от 0 до 1
+        master_volume: MASTER_VOLUME,
+        sfx_volume: SFX_VOLUME,
+        music_volume: MUSIC_VOLUME,
+        mute: MUTE,
+        sound_max_instances: SOUND_MAX_INSTANCES,
+        sound_instance_time: SOUND_INSTANCE_TIME,
+        main_unit_shoot_sound_volume: MAIN_UNIT_SHOOT_SOUND_VOLUME,
+        main_unit_impact_sound_volume: MAIN_UNIT_IMPACT_SOUND_VOLUME,
+        target_unit_impact_sound_volume: TARGET_UNIT_IMPACT_SOUND_VOLUME,
+        enemy_unit_impact_sound_volume: ENEMY_UNIT_IMPACT_SOUND_VOLUME,
+        enemy_unit_shoot_sound_volume: ENEMY_UNIT_SHOOT_SOUND_VOLUME,
+        wall_impact_sound_volume: WALL_IMPACT_SOUND_VOLUME,
+        // main_unit; скорость, перезарядка, дальность и здоровье - значения по умолчанию для уровней
+        main_unit_speed: MAIN_UNIT_SPEED,
+        main_unit_shoot_delay: MAIN_UNIT_SHOOT_DELAY,
+        main_unit_shoot_range: MAIN_UNIT_SHOOT_RANGE,
+        main_unit_hit_points: MAIN_UNIT_HIT_POINTS,
+        main_unit_radius: MAIN_UNIT_RADIUS,
+        main_unit_invulnerability_time: MAIN_UNIT_INVULNERABILITY_TIME,
+        // target_unit
+        target_unit_radius: TARGET_UNIT_RADIUS,
+        target_unit_destroyed_hit_points: TARGET_UNIT_DESTROYED_HIT_POINTS,
+        // enemy_unit; скорости и оружие - значения по умолчанию для уровней
+        enemy_unit_speed: ENEMY_UNIT_SPEED,
+        enemy_unit_rotation_speed: ENEMY_UNIT_ROTATION_SPEED,
+        enemy_unit_radius: ENEMY_UNIT_RADIUS,
+        enemy_unit_contact_damage: ENEMY_UNIT_CONTACT_DAMAGE,
+        enemy_unit_shoot_delay: ENEMY_UNIT_SHOOT_DELAY,
+        enemy_unit_shoot_range: ENEMY_UNIT_SHOOT_RANGE,
+        enemy_unit_shoot_accuracy: ENEMY_UNIT_SHOOT_ACCURACY,
+        enemy_unit_shoot_spread: ENEMY_UNIT_SHOOT_SPREAD,
+        enemy_unit_projectile_speed: ENEMY_UNIT_PROJECTILE_SPEED,
+        enemy_unit_projectile_damage: ENEMY_UNIT_PROJECTILE_DAMAGE,
+        enemy_unit_spawn_spread: ENEMY_UNIT_SPAWN_SPREAD,
+        enemy_unit_sight_range: ENEMY_UNIT_SIGHT_RANGE,
+        enemy_unit_patrol_tolerance: ENEMY_UNIT_PATROL_TOLERANCE,
+        enemy_unit_separation_radius: ENEMY_UNIT_SEPARATION_RADIUS,
+        enemy_unit_separation_weight: ENEMY_UNIT_SEPARATION_WEIGHT,
+        enemy_unit_alignment_radius: ENEMY_UNIT_ALIGNMENT_RADIUS,
+        enemy_unit_alignment_weight: ENEMY_UNIT_ALIGNMENT_WEIGHT,
+        enemy_unit_cohesion_radius: ENEMY_UNIT_COHESION_RADIUS,
+        enemy_unit_cohesion_weight: ENEMY_UNIT_COHESION_WEIGHT,
+        // очки
+        score_target_hit: SCORE_TARGET_HIT,
+        score_enemy_kill: SCORE_ENEMY_KILL,
+        score_damage_penalty: SCORE_DAMAGE_PENALTY,
+        score_clear_bonus: SCORE_CLEAR_BONUS,
+        // волны и поиск пути
+        wave_delay: WAVE_DELAY,
+        navigation_cell_size: NAVIGATION_CELL_SIZE,
+    };
+
+    /// Настройки по умолчанию, файл настроек и аргументы запуска.
+    pub async fn load() -> Self {
+        let config = Self::load_file().await.unwrap_or_default();
+        config.with_arguments()
+    }
+
+    /// Прочитать файл настроек. Файла по умолчанию может не быть.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn load_file() -> Option<Self> {
+        Self::read_file().map_err(|e| info!("{}", e)).ok().flatten()
+    }
+
+    /// Перечитать файл настроек и аргументы во время игры.
+    ///
+    /// ### Возвращаемое значение
+    ///
+    /// Ошибка, если файл не прочитался или не разобрался, например сохранен не до конца.
+    /// Тогда текущие настройки остаются.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload() -> Result<Self, String> {
+        Ok(Self::read_file()?.unwrap_or_default().with_arguments())
+    }
+
+    /// Путь файла настроек: `--config=path` или `assets/config.json`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn path() -> String {
+        let path = get_command_line_argument("config");
+        if path.is_empty() {
+            CONFIG_PATH.to_string()
+        } else {
+            path
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read_file() -> Result<Option<Self>, String> {
+        let path = Self::path();
+        if path == CONFIG_PATH && !std::path::Path::new(CONFIG_PATH).exists() {
+            return Ok(None);
+        }
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| format!("config: не удалось прочитать {}: {}", path, e))?;
+        Self::parse(&text).map(Some)
+    }
+
+    /// В браузере файла настроек нет, только URL параметры.
+    #[cfg(target_arch = "wasm32")]
+    async fn load_file() -> Option<Self> {
+        None
+    }
+
+    /// Разобрать текст файла настроек.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        serde_json::from_str(text).map_err(|e| format!("config: {}", e))
+    }
+
+    /// Переопределить поля аргументами `--<имя>=<значение>` или URL параметрами.
+    /// Значение, которое не подходит полю, пропускается.
+    fn with_arguments(self) -> Self {
+        let serde_json::Value::Object(fields) = serde_json::to_value(self).unwrap() else {
+            return self;
+        };
+        let mut config = self;
+        for name in fields.keys() {
+            let argument = get_command_line_argument(name);
+            if argument.is_empty() {
+                continue;
+            }
+            let value = serde_json::from_str(&argument)
+                .unwrap_or(serde_json::Value::String(argument.clone()));
+            let mut fields = serde_json::to_value(config).unwrap();
+            fields[name] = value;
+            match serde_json::from_value(fields) {
+                Ok(c) => config = c,
+                Err(e) => info!("config: {}={}: {}", name, argument, e),
+            }
+        }
+        config
+    }
+}
+
+/// Цвет в файле настроек - `[r, g, b, a]`
+mod color {
+    use macroquad::prelude::Color;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        <[f32; 4]>::from(*color).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        <[f32; 4]>::deserialize(deserializer).map(Color::from)
+    }
+}
@@ -0,0 +1,221 @@
+//! экраны игры и переходы между ними
+//!
+//! ```text
+//! Loading -> Menu -> Playing <-> Paused
+//!                      |  ^
+//!                      v  |
+//!                    Results
+//! ```
+
+use crate::assets::AssetsLoader;
+use crate::command::HostCommand;
+use crate::config::{config, set_config, Config};
+use crate::objectives::LevelResult;
+use crate::scene::{draw_text_lines, Scene};
+use crate::settings::{ERROR_FONT_SIZE, LOADING_BAR_SIZE};
+use macroquad::input::{is_key_pressed, is_mouse_button_pressed, KeyCode, MouseButton};
+use macroquad::prelude::{
+    clear_background, draw_rectangle, draw_rectangle_lines, draw_text, next_frame, screen_height,
+    screen_width,
+};
+
+/// Экран игры.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameState {
+    /// загрузка ресурсов и уровней
+    Loading,
+    /// ожидание начала игры
+    Menu,
+    Playing,
+    /// симуляция стоит, например, пока ученик правит код
+    Paused,
+    /// итоги попытки
+    Results,
+}
+
+/// Игра: текущий экран и сцена уровня.
+pub struct Game {
+    state: GameState,
+    scene: Option<Scene>, // появляется после загрузки
+}
+
+impl Game {
+    pub fn new() -> Self {
+        Self {
+            state: GameState::Loading,
+            scene: None,
+        }
+    }
+
+    /// Загрузить ресурсы и уровни. Пока читаются файлы, каждый кадр показывается прогресс.
+    /// Запись `--replay` воспроизводится сразу, без меню.
+    pub async fn load(&mut self) {
+        set_config(Config::load().await);
+        let mut loader = AssetsLoader::start().await;
+        while !loader.is_done() {
+            loader.update();
+            clear_background(config().ground_color);
+            draw_loading(&loader);
+            next_frame().await;
+        }
+        let scene = Scene::new(loader.finish().await).await;
+        self.state = if scene.is_replay() {
+            GameState::Playing
+        } else {
+            GameState::Menu
+        };
+        self.scene = Some(scene);
+    }
+
+    /// Обработать ввод и переходы между экранами, обновить сцену.
+    pub fn update(&mut self) {
+        let Some(scene) = &mut self.scene else {
+            return;
+        };
+        let host = scene.poll_host();
+        let enter = is_key_pressed(KeyCode::Enter);
+        let pause = is_key_pressed(KeyCode::P) || is_key_pressed(KeyCode::Escape);
+
+        self.state = match self.state {
+            GameState::Loading => GameState::Loading,
+            GameState::Menu => {
+                // выстрел со страницы тоже начинает игру
+                let start = matches!(host, Some(HostCommand::Start | HostCommand::Shoot));
+                if start || enter || is_mouse_button_pressed(MouseButton::Left) {
+                    GameState::Playing
+                } else {
+                    GameState::Menu
+                }
+            }
+            GameState::Playing => {
+                if pause || host == Some(HostCommand::Pause) {
+                    GameState::Paused
+                } else {
+                    if host == Some(HostCommand::Restart) {
+                        scene.restart_level();
+                    }
+                    scene.update();
+                    if scene.result().is_some() {
+                        GameState::Results
+                    } else {
+                        GameState::Playing
+                    }
+                }
+            }
+            GameState::Paused => {
+                if pause || enter || host == Some(HostCommand::Resume) {
+                    GameState::Playing
+                } else {
+                    GameState::Paused
+                }
+            }
+            GameState::Results => {
+                if scene.is_replay() {
+                    // запись сама начинает следующую попытку, если она записана
+                    scene.update();
+                }
+                if scene.result().is_none() {
+                    GameState::Playing
+                } else if is_key_pressed(KeyCode::R) || host == Some(HostCommand::Restart) {
+                    scene.restart_level();
+                    GameState::Playing
+                } else if enter || host == Some(HostCommand::Next) {
+                    if scene.result() == Some(LevelResult::Success) {
+                        scene.next_level();
+                    } else {
+                        scene.restart_level();
+                    }
+                    GameState::Playing
+                } else {
+                    GameState::Results
+                }
+            }
+        };
+    }
+
+    /// Отрисовать текущий экран.
+    pub fn draw(&self) {
+        clear_background(config().ground_color);
+        let Some(scene) = &self.scene else {
+            draw_text_lines(&[("loading...".to_string(), 48.)], screen_height() * 0.5);
+            return;
+        };
+        scene.draw();
+        match self.state {
+            GameState::Loading | GameState::Playing => {}
+            GameState::Menu => {
+                draw_shade();
+                let lines = [
+                    (format!("level: {}", scene.level_name()), 48.),
+                    ("Enter - play".to_string(), 32.),
+                    ("P - pause".to_string(), 32.),
+                ];
+                draw_text_lines(&lines, screen_height() * 0.4);
+            }
+            GameState::Paused => {
+                draw_shade();
+                let lines = [
+                    ("PAUSED".to_string(), 64.),
+                    ("P - continue".to_string(), 32.),
+                ];
+                draw_text_lines(&lines, screen_height() * 0.4);
+            }
+            GameState::Results => {
+                draw_shade();
+                scene.draw_results();
+            }
+        }
+        draw_errors(scene.asset_errors());
+    }
+}
+
+/// Экран загрузки: полоса прогресса и файлы, которые еще читаются
+fn draw_loading(loader: &AssetsLoader) {
+    let x = (screen_width() - LOADING_BAR_SIZE.x) * 0.5;
+    let y = screen_height() * 0.5;
+    draw_text_lines(&[("loading...".to_string(), 48.)], y - 20.);
+    let color = config().screen_text_color;
+    draw_rectangle_lines(x, y, LOADING_BAR_SIZE.x, LOADING_BAR_SIZE.y, 2., color);
+    draw_rectangle(
+        x,
+        y,
+        LOADING_BAR_SIZE.x * loader.progress(),
+        LOADING_BAR_SIZE.y,
+        color,
+    );
+    let mut y = y + LOADING_BAR_SIZE.y + ERROR_FONT_SIZE;
+    for path in loader.pending() {
+        draw_text(path, x, y, ERROR_FONT_SIZE, color);
+        y += ERROR_FONT_SIZE;
+    }
+}
+
+/// Список ошибок загрузки ассетов и настроек в левом нижнем углу окна
+fn draw_errors(errors: &[String]) {
+    if errors.is_empty() {
+        return;
+    }
+    let mut y = screen_height() - ERROR_FONT_SIZE * errors.len() as f32;
+    draw_text(
+        "errors:",
+        10.,
+        y - ERROR_FONT_SIZE,
+        ERROR_FONT_SIZE,
+        config().screen_text_color,
+    );
+    for error in errors {
+        draw_text(error, 10., y, ERROR_FONT_SIZE, config().screen_text_color);
+        y += ERROR_FONT_SIZE;
+    }
+}
+
+/// Затенение уровня под текстом экрана
+fn draw_shade() {
+    draw_rectangle(
+        0.,
+        0.,
+        screen_width(),
+        screen_height(),
+        config().screen_shade_color,
+    );
+}
@@ -0,0 +1,62 @@
+//! нативная перезагрузка: поиск измененных файлов ассетов, уровней и настроек
+//!
+//! Файлы сравниваются по времени изменения при каждом вызове `Watcher::changed`,
+//! без подписки на события файловой системы.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Время изменения файлов в папках и отдельных файлах.
+pub struct Watcher {
+    roots: Vec<PathBuf>,
+    files: BTreeMap<PathBuf, SystemTime>,
+}
+
+impl Watcher {
+    /// Запомнить текущее состояние файлов.
+    ///
+    /// ### Аргументы
+    ///
+    /// * `roots` - папки, которые просматриваются целиком, и отдельные файлы.
+    pub fn new(roots: Vec<PathBuf>) -> Self {
+        let mut files = BTreeMap::new();
+        for root in roots.iter() {
+            scan(root, &mut files);
+        }
+        Self { roots, files }
+    }
+
+    /// Файлы, которые появились или изменились с прошлого вызова.
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        let mut files = BTreeMap::new();
+        for root in self.roots.iter() {
+            scan(root, &mut files);
+        }
+        let changed = files
+            .iter()
+            .filter(|(path, time)| self.files.get(*path) != Some(*time))
+            .map(|(path, _)| path.clone())
+            .collect();
+        self.files = files;
+        changed
+    }
+}
+
+/// Записать время изменения файла `path` или всех файлов в папке `path`
+fn scan(path: &Path, files: &mut BTreeMap<PathBuf, SystemTime>) {
+    let Ok(metadata) = fs::metadata(path) else {
+        return;
+    };
+    if metadata.is_dir() {
+        let Ok(entries) = fs::read_dir(path) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            scan(&entry.path(), files);
+        }
+    } else if let Ok(time) = metadata.modified() {
+        files.insert(path.to_path_buf(), time);
+    }
+}
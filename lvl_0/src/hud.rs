@@ -0,0 +1,151 @@
+//! HUD: полоски здоровья, очки, время и текущая цель поверх уровня
+
+use crate::config::config;
+use crate::objectives::Objective;
+use crate::settings::*;
+use crate::stats::RunStats;
+use crate::world::World;
+use macroquad::prelude::{
+    draw_rectangle, draw_rectangle_lines, draw_text, screen_width, vec2, Vec2,
+};
+
+/// Отрисовать текст HUD в координатах окна.
+///
+/// ### Аргументы
+///
+/// * `world` - состояние уровня.
+/// * `stats` - статистика попытки.
+pub fn draw(world: &World, stats: &RunStats) {
+    let mut y = HUD_FONT_SIZE;
+    let mut line = |text: &str| {
+        draw_text(text, 10., y, HUD_FONT_SIZE, config().hud_color);
+        y += HUD_FONT_SIZE;
+    };
+    line(&format!(
+        "hp: {}   score: {}   time: {:.1}",
+        world.main_unit.hit_points.max(0.) as i32,
+        stats.score,
+        stats.time
+    ));
+    if world.waves.wave() > 0 {
+        line(&format!("wave: {}", world.waves.wave()));
+    }
+    if let Some(objective) = world.objectives.current() {
+        let text = match objective {
+            Objective::Survive { time } => {
+                format!("goal: survive {:.0} s", (time - stats.time).max(0.))
+            }
+            objective => format!("goal: {}", objective.describe()),
+        };
+        line(&text);
+    }
+}
+
+/// Миникарта в правом верхнем углу окна: стены, `target_unit`, enemy_units,
+/// `main_unit` и рамка видимой части поля.
+///
+/// ### Аргументы
+///
+/// * `world` - состояние уровня.
+/// * `center` - центр камеры в координатах мира.
+/// * `alpha` - доля шага симуляции между прошлым и текущим состоянием.
+pub fn draw_minimap(world: &World, center: Vec2, alpha: f32) {
+    let scale = MINIMAP_SIZE / world.size.max_element();
+    let origin = vec2(screen_width() - world.size.x * scale - 10., 10.);
+    let to_map = |position: Vec2| origin + position * scale;
+    let dot = |position: Vec2, color| {
+        let position = to_map(position) - MINIMAP_UNIT_SIZE * 0.5;
+        draw_rectangle(
+            position.x,
+            position.y,
+            MINIMAP_UNIT_SIZE,
+            MINIMAP_UNIT_SIZE,
+            color,
+        );
+    };
+
+    let size = world.size * scale;
+    draw_rectangle(origin.x, origin.y, size.x, size.y, config().minimap_color);
+    for wall in world.walls.iter() {
+        let rect = wall.rect();
+        let position = to_map(rect.point());
+        // тонкие стены не тоньше пикселя
+        let size = (rect.size() * scale).max(Vec2::ONE);
+        draw_rectangle(position.x, position.y, size.x, size.y, config().wall_color);
+    }
+    dot(world.target_unit.position, config().minimap_target_color);
+    for unit in world.enemy_units.iter() {
+        let (position, _) = unit.interpolated(alpha);
+        dot(position, config().minimap_enemy_color);
+    }
+    dot(
+        world.main_unit.interpolated_position(alpha),
+        config().unit_color,
+    );
+
+    let view = to_map(center - world.view * 0.5);
+    let view_size = world.view * scale;
+    draw_rectangle_lines(
+        view.x,
+        view.y,
+        view_size.x,
+        view_size.y,
+        1.,
+        config().unit_color,
+    );
+}
+
+/// Полоски здоровья над юнитами, в координатах мира.
+///
+/// ### Аргументы
+///
+/// * `world` - состояние уровня.
+/// * `alpha` - доля шага симуляции между прошлым и текущим состоянием.
+pub fn draw_health_bars(world: &World, alpha: f32) {
+    let main_unit = &world.main_unit;
+    draw_health_bar(
+        main_unit.interpolated_position(alpha),
+        MAIN_UNIT_SIZE.y * 0.5,
+        main_unit.hit_points,
+        main_unit.max_hit_points,
+    );
+
+    // `target_unit` уничтожен не при нуле, а при `target_unit_destroyed_hit_points`
+    let target_unit = &world.target_unit;
+    let destroyed = config().target_unit_destroyed_hit_points;
+    draw_health_bar(
+        target_unit.position,
+        target_unit.radius,
+        target_unit.hit_points - destroyed,
+        target_unit.max_hit_points - destroyed,
+    );
+
+    for unit in world.enemy_units.iter() {
+        let (position, _) = unit.interpolated(alpha);
+        draw_health_bar(position, unit.radius, unit.hit_points, unit.max_hit_points);
+    }
+}
+
+/// Полоска здоровья над точкой `position` на высоте `height`
+fn draw_health_bar(position: Vec2, height: f32, hit_points: f32, max_hit_points: f32) {
+    if max_hit_points <= 0. {
+        return;
+    }
+    let fraction = (hit_points / max_hit_points).clamp(0., 1.);
+    let x = position.x - HEALTH_BAR_SIZE.x * 0.5;
+    let y = position.y - height - HEALTH_BAR_SIZE.y * 2.;
+    draw_rectangle(
+        x,
+        y,
+        HEALTH_BAR_SIZE.x,
+        HEALTH_BAR_SIZE.y,
+        config().health_bar_back_color,
+    );
+    draw_rectangle(
+        x,
+        y,
+        HEALTH_BAR_SIZE.x * fraction,
+        HEALTH_BAR_SIZE.y,
+        config().health_bar_color,
+    );
+}
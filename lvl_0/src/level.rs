@@ -0,0 +1,166 @@
+//! описание уровня из файла `assets/levels/<name>.json`
+//!
+//! Координаты в пикселях поля, `[x, y]`. Необязательные параметры юнитов
+//! берутся из `config()` при появлении юнита, а не при чтении файла:
+//! `main_unit_speed`, `enemy_unit_speed` и т.д.
+
+use crate::objectives::Objective;
+use crate::units::enemy_unit::EnemyBehaviour;
+use crate::units::wall::WallKind;
+use macroquad::file::load_string;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Уровень: размер поля, юниты и стены.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Level {
+    pub name: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub description: String, // текст задания для страницы
+    pub size: [f32; 2],
+    /// видимая часть поля, камера следует за `main_unit`; по умолчанию поле целиком
+    #[serde(default)]
+    pub view: Option<[f32; 2]>,
+    pub main_unit: MainUnitDef,
+    pub target_unit: TargetUnitDef,
+    #[serde(default)]
+    pub enemy_units: Vec<EnemyUnitDef>,
+    /// поведение по типам enemy_units
+    #[serde(default)]
+    pub enemy_types: BTreeMap<String, EnemyBehaviour>,
+    #[serde(default)]
+    pub walls: Vec<WallDef>,
+    /// волны enemy_units, без них враги только из `enemy_units`
+    #[serde(default)]
+    pub waves: Option<WavesDef>,
+    /// цели уровня, по умолчанию уничтожить `target_unit`
+    #[serde(default = "objectives")]
+    pub objectives: Vec<Objective>,
+}
+
+/// Параметры `MainUnit`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct MainUnitDef {
+    pub position: [f32; 2],
+    #[serde(default)]
+    pub speed: Option<f32>,
+    #[serde(default)]
+    pub shoot_delay: Option<f32>,
+    #[serde(default)]
+    pub shoot_range: Option<f32>,
+    #[serde(default)]
+    pub hit_points: Option<f32>,
+}
+
+/// Параметры `TargetUnit`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TargetUnitDef {
+    pub position: [f32; 2],
+    #[serde(default = "hit_points")]
+    pub hit_points: f32,
+}
+
+/// Параметры `EnemyUnit`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct EnemyUnitDef {
+    pub position: [f32; 2],
+    #[serde(default = "hit_points")]
+    pub hit_points: f32,
+    #[serde(default)]
+    pub speed: Option<f32>,
+    #[serde(default)]
+    pub rotation_speed: Option<f32>,
+    /// тип из `enemy_types`, без типа - поведение по умолчанию
+    #[serde(default, rename = "type")]
+    pub unit_type: Option<String>,
+    #[serde(default)]
+    pub patrol: Vec<[f32; 2]>,
+    /// случайный разброс точки появления, px; 0 - точно в `position`
+    #[serde(default)]
+    pub spawn_spread: f32,
+}
+
+/// Параметры `Wall`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct WallDef {
+    pub position: [f32; 2], // центр
+    pub size: [f32; 2],
+    #[serde(default)]
+    pub kind: WallKind,
+}
+
+/// Волны enemy_units.
+/// Следующая волна появляется через `delay` после того, как поле очищено,
+/// или через `duration` текущей волны, если он задан.
+#[derive(Clone, Debug, Deserialize)]
+pub struct WavesDef {
+    #[serde(default)]
+    pub delay: Option<f32>, // пауза перед волной, с
+    /// после последней волны начать с первой
+    #[serde(default)]
+    pub endless: bool,
+    pub spawn_points: Vec<[f32; 2]>,
+    pub waves: Vec<WaveDef>,
+}
+
+/// Одна волна: группы enemy_units.
+#[derive(Clone, Debug, Deserialize)]
+pub struct WaveDef {
+    /// время до следующей волны, с; без него волна ждет, пока поле очистится
+    #[serde(default)]
+    pub duration: Option<f32>,
+    pub units: Vec<WaveGroupDef>,
+}
+
+/// Группа одинаковых enemy_units в волне.
+#[derive(Clone, Debug, Deserialize)]
+pub struct WaveGroupDef {
+    #[serde(default = "wave_group_count")]
+    pub count: usize,
+    /// номер точки из `spawn_points`, без него точки чередуются
+    #[serde(default)]
+    pub spawn_point: Option<usize>,
+    #[serde(default = "hit_points")]
+    pub hit_points: f32,
+    #[serde(default)]
+    pub speed: Option<f32>,
+    #[serde(default)]
+    pub rotation_speed: Option<f32>,
+    #[serde(default, rename = "type")]
+    pub unit_type: Option<String>,
+}
+
+impl Level {
+    /// Разобрать текст файла уровня.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        serde_json::from_str(text).map_err(|e| format!("level: {}", e))
+    }
+
+    /// Прочитать уровень `name` из `assets/levels`.
+    pub async fn load(name: &str) -> Result<Self, String> {
+        let path = format!("../assets/levels/{}.json", name);
+        let text = load_string(&path)
+            .await
+            .map_err(|e| format!("level: не удалось прочитать {}: {}", path, e))?;
+        Self::parse(&text)
+    }
+
+    /// Встроенный уровень на случай, если файл не прочитался.
+    pub fn fallback() -> Self {
+        Self::parse(include_str!("../../assets/levels/lvl_0.json")).unwrap()
+    }
+}
+
+fn hit_points() -> f32 {
+    100.
+}
+
+fn wave_group_count() -> usize {
+    1
+}
+
+fn objectives() -> Vec<Objective> {
+    vec![Objective::DestroyTarget]
+}
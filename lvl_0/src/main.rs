@@ -6,21 +6,41 @@ mod settings;
 use settings::*;
 
 mod assets;
+mod audio;
+mod camera;
+mod campaign;
 mod command;
+mod config;
+mod game;
+#[cfg(not(target_arch = "wasm32"))]
+mod hot_reload;
+mod hud;
+mod level;
+mod navigation;
+mod objectives;
+mod particles;
+mod replay;
 mod scene;
+mod stats;
 mod units;
 mod utils;
+mod waves;
+mod world;
 
-use crate::scene::Scene;
+use crate::game::Game;
 
 #[macroquad::main(window_conf)]
 async fn main() {
-    let mut scene = Scene::new().await;
+    let mut game = Game::new();
+
+    // экран загрузки, пока читаются ресурсы
+    game.draw();
+    next_frame().await;
+    game.load().await;
 
     loop {
-        scene.update();
-        clear_background(GROUND_COLOR);
-        scene.draw();
+        game.update();
+        game.draw();
         next_frame().await
     }
 }
@@ -0,0 +1,189 @@
+//! поиск пути для enemy_units: поле направлений (flow field) на сетке
+//!
+//! Поле считается одним проходом Дейкстры от клетки цели, после чего
+//! любой юнит находит путь спуском к соседней клетке с меньшим расстоянием.
+//! Пересчет только при смене клетки цели.
+
+use crate::units::wall::Wall;
+use macroquad::prelude::{Rect, Vec2};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+/// Сетка проходимости и расстояния до цели.
+pub struct FlowField {
+    cell_size: f32,
+    cols: usize,
+    rows: usize,
+    blocked: Vec<bool>,
+    distance: Vec<u32>,
+    goal: Option<usize>,
+}
+
+impl FlowField {
+    /// Создает сетку для поля.
+    ///
+    /// ### Аргументы
+    ///
+    /// * `size` - размер поля.
+    /// * `cell_size` - размер клетки.
+    /// * `walls` - стены.
+    /// * `clearance` - отступ от стен, обычно радиус юнита.
+    pub fn new(size: Vec2, cell_size: f32, walls: &[Wall], clearance: f32) -> Self {
+        let cols = (size.x / cell_size).ceil().max(1.) as usize;
+        let rows = (size.y / cell_size).ceil().max(1.) as usize;
+        let mut field = Self {
+            cell_size,
+            cols,
+            rows,
+            blocked: vec![false; cols * rows],
+            distance: vec![u32::MAX; cols * rows],
+            goal: None,
+        };
+
+        for wall in walls.iter() {
+            let rect = wall.rect();
+            let rect = Rect::new(
+                rect.x - clearance,
+                rect.y - clearance,
+                rect.w + 2. * clearance,
+                rect.h + 2. * clearance,
+            );
+            for i in 0..field.blocked.len() {
+                if rect.contains(field.center(i)) {
+                    field.blocked[i] = true;
+                }
+            }
+        }
+        field
+    }
+
+    /// Пересчитать расстояния до `goal`, если цель сменила клетку.
+    pub fn update(&mut self, goal: Vec2) {
+        let goal = self.index(goal);
+        if self.goal == Some(goal) {
+            return;
+        }
+        self.goal = Some(goal);
+
+        self.distance.fill(u32::MAX);
+        self.distance[goal] = 0;
+        let mut queue = BinaryHeap::new();
+        queue.push(Reverse((0, goal)));
+
+        while let Some(Reverse((distance, i))) = queue.pop() {
+            if distance > self.distance[i] {
+                continue;
+            }
+            for (j, cost) in self.neighbours(i) {
+                if self.blocked[j] {
+                    continue;
+                }
+                let next = distance + cost;
+                if next < self.distance[j] {
+                    self.distance[j] = next;
+                    queue.push(Reverse((next, j)));
+                }
+            }
+        }
+    }
+
+    /// Следующая точка пути от `position` к цели.
+    /// `None`, если цель недостижима.
+    pub fn waypoint(&self, position: Vec2) -> Option<Vec2> {
+        let i = self.index(position);
+        let next = self
+            .neighbours(i)
+            .into_iter()
+            .filter(|(j, _)| self.distance[*j] < self.distance[i])
+            .min_by_key(|(j, _)| (self.distance[*j], *j))?;
+        Some(self.center(next.0))
+    }
+
+    /// Путь от `position` до цели по центрам клеток, не длиннее `limit` точек.
+    pub fn path(&self, position: Vec2, limit: usize) -> Vec<Vec2> {
+        let mut path = vec![position];
+        let mut current = position;
+        while path.len() < limit {
+            match self.waypoint(current) {
+                Some(next) => {
+                    path.push(next);
+                    current = next;
+                }
+                None => break,
+            }
+        }
+        path
+    }
+
+    /// Непроходимые клетки, для отладочной отрисовки.
+    pub fn blocked_cells(&self) -> impl Iterator<Item = Rect> + '_ {
+        (0..self.blocked.len())
+            .filter(|i| self.blocked[*i])
+            .map(|i| {
+                let center = self.center(i);
+                let half = self.cell_size * 0.5;
+                Rect::new(
+                    center.x - half,
+                    center.y - half,
+                    self.cell_size,
+                    self.cell_size,
+                )
+            })
+    }
+
+    /// Номер клетки, точки вне поля прижимаются к краю.
+    fn index(&self, position: Vec2) -> usize {
+        let col = ((position.x / self.cell_size).max(0.) as usize).min(self.cols - 1);
+        let row = ((position.y / self.cell_size).max(0.) as usize).min(self.rows - 1);
+        row * self.cols + col
+    }
+
+    fn center(&self, i: usize) -> Vec2 {
+        let col = i % self.cols;
+        let row = i / self.cols;
+        Vec2::new(
+            (col as f32 + 0.5) * self.cell_size,
+            (row as f32 + 0.5) * self.cell_size,
+        )
+    }
+
+    /// Соседние клетки и цена перехода.
+    /// По диагонали нельзя срезать угол стены.
+    fn neighbours(&self, i: usize) -> Vec<(usize, u32)> {
+        let col = (i % self.cols) as isize;
+        let row = (i / self.cols) as isize;
+        let free = |c: isize, r: isize| {
+            c >= 0
+                && r >= 0
+                && (c as usize) < self.cols
+                && (r as usize) < self.rows
+                && !self.blocked[r as usize * self.cols + c as usize]
+        };
+
+        let mut result = Vec::with_capacity(8);
+        for dr in -1..=1isize {
+            for dc in -1..=1isize {
+                if dc == 0 && dr == 0 {
+                    continue;
+                }
+                let (c, r) = (col + dc, row + dr);
+                if c < 0 || r < 0 || c as usize >= self.cols || r as usize >= self.rows {
+                    continue;
+                }
+                let cost = if dc != 0 && dr != 0 {
+                    if !free(col + dc, row) || !free(col, row + dr) {
+                        continue;
+                    }
+                    DIAGONAL_COST
+                } else {
+                    STRAIGHT_COST
+                };
+                result.push((r as usize * self.cols + c as usize, cost));
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,170 @@
+//! цели уровня и условия победы и поражения
+//!
+//! Цели задаются списком `objectives` в файле уровня:
+//! ```json
+//! "objectives": [
+//!   { "type": "destroy_target" },
+//!   { "type": "kill_all_enemies" },
+//!   { "type": "survive", "time": 60 },
+//!   { "type": "hit_target_untouched" }
+//! ]
+//! ```
+//! Уровень пройден, когда выполнены все цели, и провален, когда провалена любая
+//! или уничтожен `main_unit`. Без списка цель одна - `destroy_target`.
+
+use serde::{Deserialize, Serialize};
+
+/// Цель уровня.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Objective {
+    /// уничтожить `target_unit`
+    DestroyTarget,
+    /// уничтожить всех enemy_units, включая все волны
+    KillAllEnemies,
+    /// продержаться `time` секунд
+    Survive { time: f32 },
+    /// попасть в `target_unit`, ни разу не столкнувшись с enemy_unit
+    HitTargetUntouched,
+}
+
+impl Objective {
+    /// Короткое описание для экрана.
+    pub fn describe(&self) -> String {
+        match self {
+            Objective::DestroyTarget => "destroy the target".to_string(),
+            Objective::KillAllEnemies => "kill all enemies".to_string(),
+            Objective::Survive { time } => format!("survive {} s", time),
+            Objective::HitTargetUntouched => "hit the target, don't touch enemies".to_string(),
+        }
+    }
+}
+
+/// Состояние цели.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectiveStatus {
+    InProgress,
+    Success,
+    Failure,
+}
+
+/// Итог уровня.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LevelResult {
+    Success,
+    Failure,
+}
+
+/// Что произошло на уровне к концу шага симуляции.
+pub struct ObjectiveContext {
+    pub time: f32,
+    pub target_destroyed: bool,
+    pub target_hit: bool,
+    pub enemy_contact: bool,
+    pub enemies_left: bool, // enemy_units на поле или в будущих волнах
+    pub main_unit_alive: bool,
+}
+
+/// Цель уровня вместе с состоянием, в таком виде ее получает страница.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct ObjectiveState {
+    #[serde(flatten)]
+    pub objective: Objective,
+    pub status: ObjectiveStatus,
+}
+
+/// Цели уровня и их выполнение.
+pub struct Objectives {
+    states: Vec<ObjectiveState>,
+}
+
+impl Objectives {
+    /// Пустой список - одна цель `destroy_target`.
+    pub fn new(objectives: &[Objective]) -> Self {
+        let objectives = if objectives.is_empty() {
+            &[Objective::DestroyTarget]
+        } else {
+            objectives
+        };
+        let states = objectives
+            .iter()
+            .map(|&objective| ObjectiveState {
+                objective,
+                status: ObjectiveStatus::InProgress,
+            })
+            .collect();
+        Self { states }
+    }
+
+    /// Обновить состояния целей.
+    ///
+    /// ### Возвращаемое значение
+    ///
+    /// Итог уровня, если он решился на этом шаге.
+    pub fn update(&mut self, context: &ObjectiveContext) -> Option<LevelResult> {
+        for state in self.states.iter_mut() {
+            if state.status != ObjectiveStatus::InProgress {
+                continue;
+            }
+            state.status = match state.objective {
+                Objective::DestroyTarget if context.target_destroyed => ObjectiveStatus::Success,
+                Objective::KillAllEnemies if !context.enemies_left => ObjectiveStatus::Success,
+                Objective::Survive { time } if context.time >= time => ObjectiveStatus::Success,
+                Objective::HitTargetUntouched if context.enemy_contact => ObjectiveStatus::Failure,
+                Objective::HitTargetUntouched if context.target_hit => ObjectiveStatus::Success,
+                _ => ObjectiveStatus::InProgress,
+            };
+        }
+
+        if !context.main_unit_alive
+            || self
+                .states
+                .iter()
+                .any(|s| s.status == ObjectiveStatus::Failure)
+        {
+            for state in self.states.iter_mut() {
+                if state.status == ObjectiveStatus::InProgress {
+                    state.status = ObjectiveStatus::Failure;
+                }
+            }
+            return Some(LevelResult::Failure);
+        }
+        if self
+            .states
+            .iter()
+            .all(|s| s.status == ObjectiveStatus::Success)
+        {
+            return Some(LevelResult::Success);
+        }
+        None
+    }
+
+    /// Первая невыполненная цель.
+    pub fn current(&self) -> Option<Objective> {
+        self.states
+            .iter()
+            .find(|s| s.status == ObjectiveStatus::InProgress)
+            .map(|s| s.objective)
+    }
+
+    /// Цели строками для экрана: `+` выполнена, `-` провалена.
+    pub fn lines(&self) -> Vec<String> {
+        self.states
+            .iter()
+            .map(|s| {
+                let mark = match s.status {
+                    ObjectiveStatus::InProgress => ' ',
+                    ObjectiveStatus::Success => '+',
+                    ObjectiveStatus::Failure => '-',
+                };
+                format!("[{}] {}", mark, s.objective.describe())
+            })
+            .collect()
+    }
+
+    /// Цели и их состояния в JSON для страницы.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.states).unwrap_or_default()
+    }
+}
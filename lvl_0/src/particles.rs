@@ -0,0 +1,180 @@
+//! частицы: вспышки выстрелов и попаданий
+//!
+//! Частицы только для вида и не влияют на симуляцию, поэтому у них свой
+//! генератор случайных чисел и обновляются они по времени кадра.
+
+use crate::config::config;
+use crate::settings::*;
+use macroquad::prelude::{draw_circle, vec2, Color, Vec2};
+use macroquad::rand::RandGenerator;
+
+/// Параметры вспышки частиц.
+#[derive(Clone, Copy, Debug)]
+pub struct Emitter {
+    pub count: usize,
+    pub lifetime: f32, // с
+    pub speed: f32,    // начальная скорость, px/с
+    /// разброс направления, радианы в каждую сторону; `PI` - во все стороны
+    pub spread: f32,
+    pub radius: f32, // начальный радиус, к концу жизни уменьшается до нуля
+    pub color: Color,
+    pub end_color: Color, // цвет в конце жизни
+}
+
+impl Emitter {
+    /// вспышка у ствола `main_unit`
+    pub fn shot() -> Self {
+        Self {
+            count: 6,
+            lifetime: 0.15,
+            speed: 250.,
+            spread: 0.4,
+            radius: 4.,
+            color: config().projectile_color,
+            end_color: Color::new(1., 0.6, 0.2, 0.),
+        }
+    }
+
+    /// попадание в `target_unit`
+    pub fn target_hit() -> Self {
+        Self {
+            count: 12,
+            lifetime: 0.4,
+            speed: 180.,
+            spread: std::f32::consts::PI,
+            radius: 4.,
+            color: Color::new(1., 0.85, 0.3, 1.),
+            end_color: Color::new(0.6, 0.2, 0.1, 0.),
+        }
+    }
+
+    /// попадание в `enemy_unit`
+    pub fn enemy_hit() -> Self {
+        Self {
+            count: 8,
+            lifetime: 0.3,
+            speed: 150.,
+            spread: std::f32::consts::PI,
+            radius: 3.,
+            color: Color::new(0.9, 0.3, 0.2, 1.),
+            end_color: Color::new(0.3, 0.3, 0.3, 0.),
+        }
+    }
+
+    /// снаряд остановлен стеной
+    pub fn wall_hit() -> Self {
+        Self {
+            count: 5,
+            lifetime: 0.25,
+            speed: 120.,
+            spread: std::f32::consts::PI,
+            radius: 3.,
+            color: config().wall_color,
+            end_color: Color::new(0.5, 0.5, 0.5, 0.),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Particle {
+    position: Vec2,
+    velocity: Vec2,
+    age: f32,
+    lifetime: f32, // 0 - ячейка пула свободна
+    radius: f32,
+    color: Color,
+    end_color: Color,
+}
+
+impl Particle {
+    const NONE: Self = Self {
+        position: Vec2::ZERO,
+        velocity: Vec2::ZERO,
+        age: 0.,
+        lifetime: 0.,
+        radius: 0.,
+        color: PROJECTILE_COLOR,
+        end_color: PROJECTILE_COLOR,
+    };
+
+    fn alive(&self) -> bool {
+        self.age < self.lifetime
+    }
+}
+
+/// Пул частиц постоянного размера `PARTICLES_CAPACITY`.
+/// Новые частицы занимают ячейки по кругу; когда пул полон, заменяют самые старые.
+pub struct Particles {
+    pool: Vec<Particle>,
+    next: usize, // ячейка для следующей частицы
+    rng: RandGenerator,
+}
+
+impl Particles {
+    pub fn new() -> Self {
+        Self {
+            pool: vec![Particle::NONE; PARTICLES_CAPACITY],
+            next: 0,
+            rng: RandGenerator::new(),
+        }
+    }
+
+    /// Выпустить частицы.
+    ///
+    /// ### Аргументы
+    ///
+    /// * `emitter` - параметры вспышки.
+    /// * `position` - точка появления.
+    /// * `rotation` - направление вылета, радианы, 0 - вверх, как у юнитов.
+    pub fn emit(&mut self, emitter: &Emitter, position: Vec2, rotation: f32) {
+        for _ in 0..emitter.count {
+            let angle = rotation - f32::to_radians(90.)
+                + self.rng.gen_range(-emitter.spread, emitter.spread);
+            let speed = emitter.speed * self.rng.gen_range(0.5, 1.);
+            self.pool[self.next] = Particle {
+                position,
+                velocity: vec2(angle.cos(), angle.sin()) * speed,
+                age: 0.,
+                lifetime: emitter.lifetime * self.rng.gen_range(0.7, 1.),
+                radius: emitter.radius,
+                color: emitter.color,
+                end_color: emitter.end_color,
+            };
+            self.next = (self.next + 1) % self.pool.len();
+        }
+    }
+
+    /// Сдвинуть живые частицы на время кадра `dt`.
+    pub fn update(&mut self, dt: f32) {
+        for particle in self.pool.iter_mut().filter(|p| p.alive()) {
+            particle.age += dt;
+            particle.position += particle.velocity * dt;
+            particle.velocity *= 1. - (PARTICLES_DRAG * dt).min(1.);
+        }
+    }
+
+    /// Убрать все частицы, например при перезапуске уровня.
+    pub fn clear(&mut self) {
+        self.pool.fill(Particle::NONE);
+    }
+
+    /// Отрисовать частицы в координатах мира.
+    pub fn draw(&self) {
+        for particle in self.pool.iter().filter(|p| p.alive()) {
+            let t = particle.age / particle.lifetime;
+            let (a, b) = (particle.color, particle.end_color);
+            let color = Color::new(
+                a.r + (b.r - a.r) * t,
+                a.g + (b.g - a.g) * t,
+                a.b + (b.b - a.b) * t,
+                a.a + (b.a - a.a) * t,
+            );
+            draw_circle(
+                particle.position.x,
+                particle.position.y,
+                particle.radius * (1. - t),
+                color,
+            );
+        }
+    }
+}
@@ -0,0 +1,275 @@
+//! запись и воспроизведение последовательности `Command`
+//!
+//! Формат файла текстовый, построчный:
+//! ```text
+//! seed 0
+//! config {"ground_color":[0.77,0.8,0.8,1.0],...}
+//! level lvl_0
+//! <shoot> <wasd.x> <wasd.y> <rotation> <target_point.x> <target_point.y> <auto_aim>
+//! ...
+//! restart
+//! ...
+//! level lvl_1
+//! ...
+//! ```
+//! `config` - настройки, с которыми сделана запись, JSON в одну строку.
+//! Одна строка команды на один шаг симуляции. `restart` - уровень начат заново,
+//! `level` после команд - переход на следующий уровень. Строки с `#` игнорируются.
+
+use crate::command::Command;
+use crate::config::Config;
+use macroquad::math::Vec2;
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs::File;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::BufWriter;
+use std::io::Write;
+
+/// Шаг записи: команда или смена попытки между шагами.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReplayStep {
+    Command(Command),
+    /// уровень начат заново
+    Restart,
+    /// переход на уровень с этим именем
+    Level(String),
+}
+
+/// Записанный прогон уровня.
+#[derive(Debug)]
+pub struct Replay {
+    pub seed: u64,
+    pub level: String, // первый уровень
+    /// настройки записи; в старых записях их нет
+    pub config: Option<Config>,
+    pub steps: Vec<ReplayStep>,
+}
+
+impl Replay {
+    /// Разобрать текст файла записи.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut seed = None;
+        let mut level = None;
+        let mut config = None;
+        let mut steps = vec![];
+
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let error = || format!("replay: строка {}: {:?}", n + 1, line);
+            match words[0] {
+                "seed" => {
+                    seed = Some(
+                        words
+                            .get(1)
+                            .and_then(|w| w.parse().ok())
+                            .ok_or_else(error)?,
+                    )
+                }
+                "level" => {
+                    let name = words.get(1).ok_or_else(error)?.to_string();
+                    if level.is_none() {
+                        level = Some(name);
+                    } else {
+                        steps.push(ReplayStep::Level(name));
+                    }
+                }
+                "config" => {
+                    let json = line.strip_prefix("config").unwrap_or_default();
+                    config = Some(Config::parse(json).map_err(|e| format!("replay: {}", e))?);
+                }
+                "restart" => steps.push(ReplayStep::Restart),
+                _ => steps.push(ReplayStep::Command(
+                    parse_command(&words).ok_or_else(error)?,
+                )),
+            }
+        }
+
+        Ok(Self {
+            seed: seed.ok_or("replay: нет строки `seed`")?,
+            level: level.ok_or("replay: нет строки `level`")?,
+            config,
+            steps,
+        })
+    }
+}
+
+/// Запись команд по ходу игры.
+/// Нативно запись идет в файл. В браузере файлов нет: текст копится в памяти,
+/// и страница получает его параметром `replay_record` при каждом `flush`.
+pub struct ReplayRecorder {
+    writer: Writer,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+type Writer = BufWriter<File>;
+#[cfg(target_arch = "wasm32")]
+type Writer = Vec<u8>;
+
+impl ReplayRecorder {
+    /// Создать файл записи и записать заголовок.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn create(path: &str, seed: u64, level: &str, config: &Config) -> std::io::Result<Self> {
+        Self::with_writer(BufWriter::new(File::create(path)?), seed, level, config)
+    }
+
+    /// Начать запись в памяти, `path` в браузере не используется.
+    #[cfg(target_arch = "wasm32")]
+    pub fn create(_path: &str, seed: u64, level: &str, config: &Config) -> std::io::Result<Self> {
+        Self::with_writer(vec![], seed, level, config)
+    }
+
+    fn with_writer(
+        mut writer: Writer,
+        seed: u64,
+        level: &str,
+        config: &Config,
+    ) -> std::io::Result<Self> {
+        writeln!(writer, "seed {}", seed)?;
+        writeln!(writer, "config {}", serde_json::to_string(config)?)?;
+        writeln!(writer, "level {}", level)?;
+        Ok(Self { writer })
+    }
+
+    /// Записать команду одного шага.
+    pub fn record(&mut self, command: &Command) -> std::io::Result<()> {
+        writeln!(
+            self.writer,
+            "{} {} {} {} {} {} {}",
+            command.shoot as u8,
+            command.wasd.x,
+            command.wasd.y,
+            command.rotation,
+            command.target_point.x,
+            command.target_point.y,
+            command.auto_aim as u8,
+        )
+    }
+
+    /// Записать, что уровень начат заново.
+    pub fn restart(&mut self) -> std::io::Result<()> {
+        writeln!(self.writer, "restart")
+    }
+
+    /// Записать переход на уровень `name`.
+    pub fn level(&mut self, name: &str) -> std::io::Result<()> {
+        writeln!(self.writer, "level {}", name)
+    }
+
+    /// Сбросить буфер на диск.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Передать текст записи странице.
+    #[cfg(target_arch = "wasm32")]
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        quad_url::set_program_parameter("replay_record", &String::from_utf8_lossy(&self.writer));
+        Ok(())
+    }
+}
+
+fn parse_vec2(words: &[&str]) -> Option<Vec2> {
+    match words {
+        [x, y] => Some(Vec2::new(x.parse().ok()?, y.parse().ok()?)),
+        _ => None,
+    }
+}
+
+fn parse_bool(word: &str) -> Option<bool> {
+    match word {
+        "0" => Some(false),
+        "1" => Some(true),
+        _ => None,
+    }
+}
+
+fn parse_command(words: &[&str]) -> Option<Command> {
+    match words {
+        [shoot, wx, wy, rotation, tx, ty, auto_aim] => Some(Command {
+            shoot: parse_bool(shoot)?,
+            wasd: parse_vec2(&[wx, wy])?,
+            rotation: rotation.parse().ok()?,
+            target_point: parse_vec2(&[tx, ty])?,
+            auto_aim: parse_bool(auto_aim)?,
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recorded_replay_parses_back() {
+        let path = std::env::temp_dir().join("replay_round_trip.txt");
+        let path = path.to_str().unwrap();
+        let config = Config {
+            mute: true,
+            ..Config::default()
+        };
+        let commands = [
+            Command {
+                shoot: true,
+                wasd: Vec2::new(-1., 0.5),
+                rotation: -1.25,
+                target_point: Vec2::new(-30.5, 400.),
+                auto_aim: false,
+            },
+            Command {
+                shoot: false,
+                auto_aim: true,
+                ..Command::new()
+            },
+        ];
+
+        let mut recorder = ReplayRecorder::create(path, 42, "lvl_0", &config).unwrap();
+        recorder.record(&commands[0]).unwrap();
+        recorder.restart().unwrap();
+        recorder.record(&commands[1]).unwrap();
+        recorder.level("lvl_1").unwrap();
+        recorder.flush().unwrap();
+        drop(recorder);
+
+        let text = std::fs::read_to_string(path).unwrap();
+        let _ = std::fs::remove_file(path);
+        let replay = Replay::parse(&format!("# комментарий\n{}\n  # еще один\n", text)).unwrap();
+
+        assert_eq!(replay.seed, 42);
+        assert_eq!(replay.level, "lvl_0");
+        assert_eq!(
+            serde_json::to_string(&replay.config.unwrap()).unwrap(),
+            serde_json::to_string(&config).unwrap()
+        );
+        assert_eq!(
+            replay.steps,
+            vec![
+                ReplayStep::Command(commands[0]),
+                ReplayStep::Restart,
+                ReplayStep::Command(commands[1]),
+                ReplayStep::Level("lvl_1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_seed_or_level_is_error() {
+        assert!(Replay::parse("level lvl_0\n0 0 0 0 0 0 0")
+            .unwrap_err()
+            .contains("seed"));
+        assert!(Replay::parse("seed 1\n0 0 0 0 0 0 0")
+            .unwrap_err()
+            .contains("level"));
+    }
+
+    #[test]
+    fn bad_command_line_is_error() {
+        assert!(Replay::parse("seed 1\nlevel lvl_0\n2 0 0 0 0 0 0").is_err());
+        assert!(Replay::parse("seed 1\nlevel lvl_0\n1 0 0 0 0 0").is_err());
+    }
+}
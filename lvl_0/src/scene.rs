@@ -1,96 +1,217 @@
 use crate::assets::Assets;
-use crate::command::Command;
-use crate::settings::ENEMY_UNIT_IMPACT_SOUND_VOLUME;
-use crate::units::enemy_unit::EnemyUnit;
-use crate::units::main_unit::MainUnit;
-use crate::units::projectile::Projectile;
-use crate::units::target_unit::TargetUnit;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::assets::ASSETS_DIR;
+use crate::audio::{Audio, Sfx};
+use crate::camera::{fit_camera, follow_center};
+use crate::campaign::Campaign;
+use crate::command::{Command, HostCommand};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::config::Config;
+use crate::config::{config, set_config};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::hot_reload::Watcher;
+use crate::hud;
+use crate::level::Level;
+use crate::objectives::LevelResult;
+use crate::particles::{Emitter, Particles};
+use crate::replay::{Replay, ReplayRecorder, ReplayStep};
+use crate::settings::*;
+use crate::stats::RunStats;
+use crate::units::projectile::Team;
+use crate::units::wall::WallKind;
 use crate::utils::get_command_line_argument;
-use macroquad::audio::{self, PlaySoundParams};
-use macroquad::input::{is_key_down, KeyCode};
-use macroquad::prelude::{info, mouse_position, screen_height, screen_width, Vec2};
+use crate::world::{Event, World};
+use macroquad::file::load_string;
+use macroquad::input::{is_key_down, is_key_pressed, is_mouse_button_down, KeyCode, MouseButton};
+use macroquad::prelude::{
+    draw_line, draw_rectangle, draw_text, info, measure_text, mouse_position, screen_height,
+    screen_width, set_camera, set_default_camera, Camera2D, Vec2,
+};
 use macroquad::time::get_frame_time;
 use quad_url::set_program_parameter;
+use std::iter::Peekable;
 
+/// Сцена: ввод, звук и отрисовка поверх `World`.
 pub struct Scene {
-    main_unit: MainUnit,
-    target_unit: TargetUnit,
-    enemy_units: Vec<EnemyUnit>,
-    projectiles: Vec<Projectile>,
+    world: World,
+    campaign: Campaign,
     dt: f32,
+    accumulator: f32, // время, еще не отработанное симуляцией
     assets: Assets,
     command: Command,
     tick: f32,
     target_point: Vec2,
+    bot_shot: bool, // выстрел по URL команде ждет перезарядки
+    replay: Option<Peekable<std::vec::IntoIter<ReplayStep>>>, // воспроизводимые шаги
+    recorder: Option<ReplayRecorder>,
+    debug: bool, // отладочный слой, F3
+    stats: RunStats,
+    particles: Particles,
+    audio: Audio,
+    #[cfg(not(target_arch = "wasm32"))]
+    watcher: Watcher, // измененные файлы для перезагрузки
 }
 
 impl Scene {
-    /// создание экземпляра Сцены
-    pub async fn new() -> Self {
-        let spawn_position = Vec2::new(screen_width() * 0.5, screen_height() * 0.8);
-        let target_unit_position = Vec2::new(screen_width() * 0.5, 160.);
-
-        let mouse_position: Vec2 = mouse_position().into();
+    /// создание экземпляра Сцены с загруженными ассетами
+    pub async fn new(assets: Assets) -> Self {
         let dt = get_frame_time();
-        let assets = Assets::new().await.unwrap();
+        let mut seed = get_command_line_argument("seed")
+            .parse()
+            .unwrap_or(DEFAULT_SEED);
+        let mut level_name = get_command_line_argument("level");
+        let unlocked = get_command_line_argument("unlocked").parse().unwrap_or(1);
+
+        // воспроизведение записи `--replay=path`
+        let replay = match load_replay(&get_command_line_argument("replay")).await {
+            Some(replay) => {
+                // уровни ниже читаются уже с настройками записи
+                if let Some(config) = replay.config {
+                    set_config(config);
+                }
+                seed = replay.seed;
+                level_name = replay.level;
+                Some(replay.steps.into_iter().peekable())
+            }
+            None => None,
+        };
+
+        let mut campaign = Campaign::load(unlocked).await;
+        let level = if level_name.is_empty() {
+            campaign.current().clone()
+        } else if campaign.contains(&level_name) {
+            // запись открывает уровень, на котором была сделана
+            if !campaign.select(&level_name, replay.is_some()) {
+                info!("level: {} еще закрыт", level_name);
+            }
+            campaign.current().clone()
+        } else {
+            campaign.leave();
+            Level::load(&level_name).await.unwrap_or_else(|e| {
+                info!("{}", e);
+                Level::fallback()
+            })
+        };
+
+        // запись прогона `--record=path`
+        let record_path = get_command_line_argument("record");
+        let recorder = if record_path.is_empty() {
+            None
+        } else {
+            match ReplayRecorder::create(&record_path, seed, &level.name, &config()) {
+                Ok(recorder) => Some(recorder),
+                Err(e) => {
+                    info!("replay: не удалось создать {}: {}", record_path, e);
+                    None
+                }
+            }
+        };
 
         let mut scene = Self {
-            main_unit: MainUnit::new(assets.main_unit_texture.clone(), spawn_position),
-            target_unit: TargetUnit::new(
-                assets.target_unit_texture.clone(),
-                assets.target_unit_shadow_texture.clone(),
-                assets.target_impact_sound.clone(),
-                target_unit_position,
-            ),
-            enemy_units: Vec::new(),
-            projectiles: vec![],
+            world: World::new(level, seed),
+            campaign,
             dt,
+            accumulator: 0.,
             assets,
             command: Command::new(),
-            tick: 1000., // большое число, чтобы сразу срабатывало
-            target_point: mouse_position,
+            tick: 1000.,              // большое число, чтобы сразу срабатывало
+            target_point: Vec2::ZERO, // точки от страницы пока нет, целимся мышью
+            bot_shot: false,
+            replay,
+            recorder,
+            debug: false,
+            stats: RunStats::new(),
+            particles: Particles::new(),
+            audio: Audio::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            watcher: Watcher::new(vec![ASSETS_DIR.into(), Config::path().into()]),
         };
-        scene.start();
+        if let Some(music) = &scene.assets.music {
+            scene.audio.play_music(music);
+        }
+        scene.set_level_to_url_query();
         scene
     }
 
-    /// запустить игру.
-    fn start(&mut self) {
-        // спавн `enemy_units`
-        // слево
-        self.spawn_single_enemy_unit(-100., 0.);
-        self.spawn_single_enemy_unit(-200., 0.);
-        // справа
-        self.spawn_single_enemy_unit(100., 0.);
-        self.spawn_single_enemy_unit(200., 0.);
-        // впереди
-        // self.spawn_single_enemy_unit(0., 100.);
+    /// передать описание уровня и прогресс кампании в URL аргументы
+    fn set_level_to_url_query(&self) {
+        let level = self.world.level();
+        set_program_parameter("level", &level.name);
+        set_program_parameter("level_title", &level.title);
+        set_program_parameter("level_description", &level.description);
+        set_program_parameter("unlocked", &self.campaign.unlocked().to_string());
+    }
+
+    /// Камера уровня: весь уровень в окне любого размера.
+    fn camera(&self) -> Camera2D {
+        let world = &self.world;
+        let alpha = self.alpha();
+        let center = follow_center(
+            world.main_unit.interpolated_position(alpha),
+            world.view,
+            world.size,
+        );
+        fit_camera(center, world.view)
+    }
+
+    /// Доля шага между двумя последними шагами симуляции для интерполяции.
+    /// После итога уровня в `accumulator` может остаться больше шага, поэтому не больше 1.
+    fn alpha(&self) -> f32 {
+        (self.accumulator / FIXED_DT).clamp(0., 1.)
     }
 
-    /// перезапустить игру
-    /// здоровье юнитов и позиции будут восстановленны
-    fn restart(&mut self) {
-        // очистить поле
-        self.enemy_units = vec![];
+    /// Ассеты, которые не удалось загрузить.
+    pub fn asset_errors(&self) -> &[String] {
+        &self.assets.errors
+    }
 
-        // восстановить `target_unit`
-        self.target_unit.hit_points = 100.;
-        self.target_unit.alive = true;
+    /// Уровень идет по записи `--replay`.
+    pub fn is_replay(&self) -> bool {
+        self.replay.is_some()
+    }
 
-        self.start();
+    /// Название уровня.
+    pub fn level_name(&self) -> &str {
+        &self.world.level().name
     }
 
-    /// создать enemy_unit по координатам относительно `target_unit`
-    fn spawn_single_enemy_unit(&mut self, dx: f32, dy: f32) {
-        let x = self.target_unit.position.x + dx;
-        let y = self.target_unit.position.y + dy;
-        let unit = EnemyUnit::new(
-            self.assets.enemy_unit_gray.clone(),
-            self.assets.target_unit_shadow_texture.clone(),
-            self.assets.target_impact_sound.clone(),
-            Vec2 { x, y },
-        );
-        self.enemy_units.push(unit);
+    /// Итог уровня, `None` - уровень еще идет.
+    pub fn result(&self) -> Option<LevelResult> {
+        self.world.result()
+    }
+
+    /// Уровень пройден: следующий уровень кампании или повтор текущего.
+    pub fn next_level(&mut self) {
+        self.stats = RunStats::new();
+        self.particles.clear();
+        self.accumulator = 0.;
+        let seed = self.world.seed();
+        match self.campaign.complete_current() {
+            Some(level) => {
+                info!("level: пройден, следующий {}", level.name);
+                self.world = World::new(level.clone(), seed);
+                self.set_level_to_url_query();
+                let name = self.world.level().name.clone();
+                self.record(|recorder| recorder.level(&name));
+            }
+            None => {
+                self.world.restart();
+                self.record(ReplayRecorder::restart);
+            }
+        }
+        set_program_parameter("result", "");
+    }
+
+    /// Начать уровень заново.
+    pub fn restart_level(&mut self) {
+        info!("level: заново {}", self.world.level().name);
+        self.world.restart();
+        self.record(ReplayRecorder::restart);
+        self.stats = RunStats::new();
+        self.particles.clear();
+        self.accumulator = 0.;
+        set_program_parameter("game_over", "false");
+        set_program_parameter("result", "");
     }
 
     /// Поймать активность пользователя.
@@ -110,35 +231,39 @@ impl Scene {
         if is_key_down(KeyCode::Down) || is_key_down(KeyCode::S) {
             y_move += 1f32;
         }
+        self.command.wasd = Vec2::new(x_move, y_move);
 
-        if self.main_unit.position.x < 1f32 {
-            x_move = 1f32;
-        }
-        if self.main_unit.position.x > screen_width() {
-            x_move = -1f32;
+        let mouse_down = is_mouse_button_down(MouseButton::Left);
+        if x_move != 0. || y_move != 0. || mouse_down {
+            self.command.auto_aim = false;
         }
+        self.command.shoot = mouse_down || self.bot_shot;
 
-        if self.main_unit.position.y < 1f32 {
-            y_move = 1f32;
-        }
-        if self.main_unit.position.y > screen_height() {
-            y_move = -1f32;
-        }
-        self.command.wasd = Vec2::new(x_move, y_move);
+        self.command.target_point = if self.target_point.x != 0. || self.target_point.y != 0. {
+            self.target_point
+        } else {
+            self.camera().screen_to_world(mouse_position().into())
+        };
     }
 
-    /// Обновить `Command` из URL аргументов
-    fn update_command_from_url_query(&mut self) {
-        if get_command_line_argument("command") == *"Shoot" {
-            // self.restart();
-            self.command.shoot = true;
-            let x = get_command_line_argument("target_point_x").parse().unwrap_or(0.);
-            let y = get_command_line_argument("target_point_y").parse().unwrap_or(0.);
+    /// Обновить `Command` из URL аргументов.
+    /// Выстрел обрабатывается здесь, остальные команды страницы возвращаются.
+    fn update_command_from_url_query(&mut self) -> Option<HostCommand> {
+        let command = HostCommand::parse(&get_command_line_argument("command"));
+        if command.is_some() {
+            set_program_parameter("command", "");
+        }
+        if command == Some(HostCommand::Shoot) {
+            self.bot_shot = true;
+            let x = get_command_line_argument("target_point_x")
+                .parse()
+                .unwrap_or(0.);
+            let y = get_command_line_argument("target_point_y")
+                .parse()
+                .unwrap_or(0.);
             self.target_point = Vec2::new(x, y);
             info!("{:?}", self.target_point);
-            set_program_parameter("command", "");
-            self.main_unit.shoot_timer = 1.; // чтобы получить выстрел с минимальной задержкой
-            self.main_unit.auto_aim = true;
+            self.command.auto_aim = true;
         }
 
         match get_command_line_argument("rotation").parse::<f32>() {
@@ -149,173 +274,476 @@ impl Scene {
                 // info!("{}", _e);
             }
         }
+        command
     }
 
     /// передать параметры в URL аргементы
     fn set_parameters_to_url_query(&mut self) {
+        let world = &self.world;
         let line = format!(
             "({}, {})",
-            self.target_unit.position.x as i32, self.target_unit.position.y as i32
+            world.target_unit.position.x as i32, world.target_unit.position.y as i32
         );
         set_program_parameter("target_pos", line.as_str());
         let line = format!(
             "({}, {})",
-            self.main_unit.position.x as i32, self.main_unit.position.y as i32
+            world.main_unit.position.x as i32, world.main_unit.position.y as i32
         );
         set_program_parameter("unit_pos", line.as_str());
+        set_program_parameter(
+            "unit_hit_points",
+            &(world.main_unit.hit_points as i32).to_string(),
+        );
 
         let mut line = "[".to_string();
-        for i in 0..self.enemy_units.len() {
+        for unit in world.enemy_units.iter() {
+            let value = format!("({}, {}), ", unit.position.x as i32, unit.position.y as i32);
+            line += &value;
+        }
+        line += "]";
+        set_program_parameter("enemy_units", &line);
+
+        // снаряды enemy_units: положение и скорость, px/с
+        let mut line = "[".to_string();
+        for p in world.projectiles.iter().filter(|p| p.team == Team::Enemy) {
+            let angle = p.rotation - f32::to_radians(90.);
+            let velocity = p.speed * Vec2::new(angle.cos(), angle.sin());
             let value = format!(
-                "({}, {}), ",
-                self.enemy_units[i].position.x as i32, self.enemy_units[i].position.y as i32
+                "({}, {}, {}, {}), ",
+                p.position.x as i32, p.position.y as i32, velocity.x as i32, velocity.y as i32
             );
             line += &value;
         }
         line += "]";
-        set_program_parameter("enemy_units", &line);
+        set_program_parameter("enemy_projectiles", &line);
+        set_program_parameter("seed", &world.seed().to_string());
+        set_program_parameter("wave", &world.waves.wave().to_string());
+        set_program_parameter("stats", &self.stats.to_json());
+        set_program_parameter("objectives", &world.objectives.to_json());
     }
 
-    /// Обновить сцену
-    pub fn update(&mut self) {
-        self.tick += self.dt;
-        self.update_command_from_user_input();
-
-        if self.tick >= 1. {
-            self.tick = 0.0;
-            self.set_parameters_to_url_query();
-            self.update_command_from_url_query();
+    /// Обмен с страницей раз в секунду: параметры уровня и команда.
+    /// Нативно заодно перечитываются измененные файлы.
+    /// Вызывается на любом экране, не только во время игры.
+    ///
+    /// ### Возвращаемое значение
+    ///
+    /// Команда страницы, если она пришла.
+    pub fn poll_host(&mut self) -> Option<HostCommand> {
+        self.tick += get_frame_time();
+        if self.tick < 1. {
+            return None;
         }
-        self.dt = get_frame_time();
-        self.target_unit.shift = Vec2::new(0., 0.);
-
-        // стрельба и спавн выстрела
-        self.main_unit_shoot();
-
-        // удалить дохлые юниты
-        self.remove_dead_enemy_units();
-
-        // обновить всех коричневыз
-        self.update_enemy_units();
-
-        // Удаление снарядов на отлете
-        self.remove_projectile_out_of_range();
+        self.tick = 0.0;
+        #[cfg(not(target_arch = "wasm32"))]
+        self.hot_reload();
+        self.set_parameters_to_url_query();
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(e) = recorder.flush() {
+                info!("replay: ошибка записи: {}", e);
+            }
+        }
+        if self.replay.is_none() {
+            self.update_command_from_url_query()
+        } else {
+            None
+        }
+    }
 
-        // поражение главной мишени
-        self.target_unit_hit();
+    /// Перечитать измененные ассеты, уровни и настройки.
+    /// Правка текущего уровня начинает его заново, остальное применяется без перезапуска.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn hot_reload(&mut self) {
+        self.assets.update();
+        let config_path = std::path::PathBuf::from(Config::path());
+        for path in self.watcher.changed() {
+            if path == config_path {
+                self.reload_config();
+                continue;
+            }
+            let Ok(file) = path.strip_prefix(ASSETS_DIR) else {
+                continue;
+            };
+            let file = file.to_string_lossy().replace('\\', "/");
+            if file == "manifest.json" {
+                self.assets.reload_manifest();
+            } else if file == "levels/campaign.json" {
+                info!("campaign: порядок уровней применится после перезапуска");
+            } else if let Some(name) = file
+                .strip_prefix("levels/")
+                .and_then(|name| name.strip_suffix(".json"))
+            {
+                self.reload_level(&path, name);
+            } else if file != "config.json" {
+                self.assets.reload(&file);
+            }
+        }
+    }
 
-        // поражение enemy_units
-        self.enemy_units_hit();
+    /// Перечитать настройки. Если файл не разобрался, текущие настройки остаются,
+    /// а ошибка показывается на экране.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reload_config(&mut self) {
+        if let Some(reason) = self.reload_blocked() {
+            info!("config: не перечитан, {}", reason);
+            return;
+        }
+        let errors = &mut self.assets.errors;
+        errors.retain(|e| !e.starts_with("config: "));
+        match Config::reload() {
+            Ok(config) => {
+                info!("config: перечитан {}", Config::path());
+                set_config(config);
+                self.audio.apply_config();
+            }
+            Err(e) => {
+                info!("{}", e);
+                errors.push(e);
+            }
+        }
     }
 
-    /// стрельба и спавн выстрела
-    fn main_unit_shoot(&mut self) {
-        let target_point = if self.target_point.x != 0. || self.target_point.y != 0. {
-            self.target_point
-        } else {
-            mouse_position().into()
+    /// Перечитать уровень `name` из файла `path`.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reload_level(&mut self, path: &std::path::Path, name: &str) {
+        if let Some(reason) = self.reload_blocked() {
+            info!("level: {} не перечитан, {}", name, reason);
+            return;
+        }
+        let level = match std::fs::read_to_string(path)
+            .map_err(|e| format!("level: не удалось прочитать {}: {}", path.display(), e))
+            .and_then(|text| Level::parse(&text))
+        {
+            Ok(level) => level,
+            Err(e) => {
+                info!("{}", e);
+                return;
+            }
         };
-
-        self.main_unit
-            .update(self.dt, target_point, &mut self.command);
-        if self.command.shoot {
-            let position = Vec2::new(
-                // точка появления выстрела
-                self.main_unit.position.x
-                    + 65. * (self.main_unit.rotation - f32::to_radians(90.)).cos(),
-                self.main_unit.position.y
-                    + 65. * (self.main_unit.rotation - f32::to_radians(90.)).sin(),
-            );
-
-            let projectile = Projectile::new(
-                self.assets.projectile_texture.clone(),
-                self.assets.main_unit_shoot_sound.clone(),
-                self.main_unit.rotation,
-                position,
-                self.main_unit.speed * 3.,
-            );
-            self.projectiles.push(projectile);
+        self.campaign.replace(level.clone());
+        if self.world.level().name == name {
+            info!("level: перечитан {}", name);
+            self.world = World::new(level, self.world.seed());
+            self.stats = RunStats::new();
+            self.particles.clear();
+            self.accumulator = 0.;
+            self.set_level_to_url_query();
         }
     }
 
-    /// Обновить все `enemy_units`
-    fn update_enemy_units(&mut self) {
-        for i in 0..self.enemy_units.len() {
-            let units = self.enemy_units.clone();
-            self.enemy_units[i].update(self.dt, self.main_unit.position, units, i);
+    /// Почему нельзя перечитать уровни и настройки: запись `--record` и
+    /// воспроизведение `--replay` должны идти на тех же уровнях и настройках,
+    /// с которыми начались, иначе повтор разойдется с записью.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reload_blocked(&self) -> Option<&'static str> {
+        if self.replay.is_some() {
+            Some("идет воспроизведение записи")
+        } else if self.recorder.is_some() {
+            Some("идет запись")
+        } else {
+            None
         }
     }
 
-    /// удалить дохлые юниты
-    fn remove_dead_enemy_units(&mut self) {
-        self.enemy_units.retain(|u| u.hit_points > 0.);
+    /// Записать строку в `--record`. При ошибке запись прекращается.
+    fn record(&mut self, write: impl FnOnce(&mut ReplayRecorder) -> std::io::Result<()>) {
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(e) = write(recorder) {
+                info!("replay: ошибка записи: {}", e);
+                self.recorder = None;
+            }
+        }
     }
 
-    /// Удаление снарядов на отлете
-    fn remove_projectile_out_of_range(&mut self) {
-        self.projectiles.retain(|p| {
-            ((p.start_position.x - p.position.x).powf(2f32)
-                + (p.start_position.y - p.position.y).powf(2f32)
-                < self.main_unit.shoot_range.powf(2f32))
-                && p.alive
-        });
+    /// Применить смены попытки из записи, которые идут перед следующей командой.
+    fn apply_replay_markers(&mut self) {
+        loop {
+            let step = match self.replay.as_mut().and_then(|replay| replay.peek()) {
+                Some(ReplayStep::Command(_)) | None => return,
+                Some(step) => step.clone(),
+            };
+            if let Some(replay) = &mut self.replay {
+                replay.next();
+            }
+            match step {
+                ReplayStep::Restart => self.restart_level(),
+                ReplayStep::Level(name) => {
+                    if self.campaign.select(&name, true) {
+                        info!("replay: уровень {}", name);
+                        self.world = World::new(self.campaign.current().clone(), self.world.seed());
+                        self.stats = RunStats::new();
+                        self.particles.clear();
+                        self.accumulator = 0.;
+                        self.set_level_to_url_query();
+                        self.record(|recorder| recorder.level(&name));
+                        set_program_parameter("result", "");
+                    } else {
+                        info!("replay: уровня {} нет в кампании", name);
+                    }
+                }
+                ReplayStep::Command(_) => unreachable!(),
+            }
+        }
     }
 
-    /// поражение главной мишени
-    fn target_unit_hit(&mut self) {
-        for i in 0..self.projectiles.len() {
-            let p = &mut self.projectiles[i];
+    /// Обновить сцену
+    /// Симуляция идет фиксированными шагами `FIXED_DT` независимо от частоты кадров.
+    pub fn update(&mut self) {
+        if self.replay.is_none() {
+            self.update_command_from_user_input();
+        }
+        if is_key_pressed(KeyCode::F3) {
+            self.debug = !self.debug;
+        }
+        if is_key_pressed(KeyCode::M) {
+            self.audio.toggle_mute();
+        }
+        self.dt = get_frame_time().min(MAX_FRAME_TIME);
+        self.particles.update(self.dt);
+        self.audio.update(self.dt);
+        // запись продолжается и после итогов, если дальше в ней новая попытка
+        self.apply_replay_markers();
+        if self.world.result().is_some() {
+            // уровень закончен, итоги показывает `Game`
+            return;
+        }
 
-            if (p.position.x - self.target_unit.position.x).powf(2f32)
-                + (p.position.y - self.target_unit.position.y).powf(2f32)
-                < self.target_unit.radius.powf(2f32)
-            {
-                p.alive = false;
-                self.target_unit.update(true, -20., p.rotation);
-                info!("target_unit.hit_points: {:?}", self.target_unit.hit_points);
-                if self.target_unit.hit_points <= -100. {
-                    self.restart();
+        self.accumulator += self.dt;
+        while self.accumulator >= FIXED_DT {
+            self.step();
+            self.accumulator -= FIXED_DT;
+            if self.world.result().is_some() {
+                // лишние шаги в том же кадре не записываются и не идут во время попытки
+                break;
+            }
+        }
+    }
+
+    /// Один шаг симуляции: команда из записи или от игрока.
+    fn step(&mut self) {
+        self.apply_replay_markers();
+        if let Some(replay) = &mut self.replay {
+            match replay.next() {
+                Some(ReplayStep::Command(command)) => self.command = command,
+                _ => {
+                    info!("replay: запись закончилась");
+                    self.replay = None;
                 }
-            } else {
-                p.update(self.dt);
             }
         }
+        let command = self.command;
+        self.record(|recorder| recorder.record(&command));
+
+        self.world.step(FIXED_DT, &self.command);
+        self.stats.update(FIXED_DT);
+        self.handle_events();
     }
 
-    /// поражение enemy_units
-    fn enemy_units_hit(&mut self) {
-        for i in 0..self.projectiles.len() {
-            let p = &mut self.projectiles[i];
-            for j in 0..self.enemy_units.len() {
-                let u = &mut self.enemy_units[j];
-                let dx = p.position.x - u.position.x;
-                let dy = p.position.y - u.position.y;
-                let dist = (dx.powf(2.) + dy.powf(2.)).sqrt();
-                if dist < u.radius {
-                    u.hit_points -= 20.;
-                    audio::play_sound_once(
-                        &u.impact_sound,
+    /// Обработать события шага симуляции: звук и переход между уровнями
+    fn handle_events(&mut self) {
+        for event in self.world.drain_events() {
+            self.stats.record(&event);
+            match event {
+                Event::MainUnitShoot { position } => {
+                    self.particles
+                        .emit(&Emitter::shot(), position, self.world.main_unit.rotation);
+                    if self.bot_shot {
+                        // выстрел по команде одиночный
+                        self.bot_shot = false;
+                        self.command.shoot = false;
+                    }
+                    self.audio.play(
+                        Sfx::MainUnitShoot,
+                        self.assets.main_unit_shoot_sound.as_ref(),
+                        config().main_unit_shoot_sound_volume,
+                    );
+                }
+                Event::TargetUnitHit { position, alive } => {
+                    self.particles.emit(&Emitter::target_hit(), position, 0.);
+                    let volume = if alive {
+                        config().target_unit_impact_sound_volume
+                    } else {
+                        config().target_unit_impact_sound_volume * 0.25
+                    };
+                    self.audio.play(
+                        Sfx::TargetUnitImpact,
+                        self.assets.target_impact_sound.as_ref(),
+                        volume,
                     );
-
-                    let da = u.rotation - p.rotation;
-                    p.alive = false;
-                    u.rotation += (da.abs() / da) * f32::to_radians(20.);
+                }
+                Event::EnemyUnitHit { position, killed } => {
+                    self.particles.emit(&Emitter::enemy_hit(), position, 0.);
+                    if killed {
+                        self.particles.emit(&Emitter::enemy_hit(), position, 0.);
+                    }
+                    self.audio.play(
+                        Sfx::EnemyUnitImpact,
+                        self.assets.target_impact_sound.as_ref(),
+                        config().enemy_unit_impact_sound_volume,
+                    );
+                }
+                Event::EnemyUnitShoot { .. } => {
+                    self.audio.play(
+                        Sfx::EnemyUnitShoot,
+                        self.assets.main_unit_shoot_sound.as_ref(),
+                        config().enemy_unit_shoot_sound_volume,
+                    );
+                }
+                Event::MainUnitHit { .. } => {
+                    self.audio.play(
+                        Sfx::MainUnitImpact,
+                        self.assets.target_impact_sound.as_ref(),
+                        config().main_unit_impact_sound_volume,
+                    );
+                }
+                Event::GameOver => {
+                    info!("level: провален");
+                    set_program_parameter("game_over", "true");
+                    set_program_parameter("result", "failure");
+                    set_program_parameter("stats", &self.stats.to_json());
+                    set_program_parameter("objectives", &self.world.objectives.to_json());
+                }
+                Event::WaveStart { wave } => {
+                    info!("wave: {}", wave);
+                }
+                Event::WallHit { position } => {
+                    self.particles.emit(&Emitter::wall_hit(), position, 0.);
+                    self.audio.play(
+                        Sfx::WallImpact,
+                        self.assets.wall_impact_sound.as_ref(),
+                        config().wall_impact_sound_volume,
+                    );
+                }
+                Event::LevelComplete => {
+                    info!("level: пройден, {}", self.stats.to_json());
+                    set_program_parameter("result", "success");
+                    set_program_parameter("stats", &self.stats.to_json());
+                    set_program_parameter("objectives", &self.world.objectives.to_json());
                 }
             }
         }
     }
 
     /// отрисовка
+    /// Положения юнитов интерполируются между двумя последними шагами симуляции.
     pub fn draw(&self) {
-        self.target_unit.draw_shadow();
-        self.main_unit.draw();
-        for i in 0..self.enemy_units.len() {
-            self.enemy_units[i].draw_shadow();
-            self.enemy_units[i].draw();
+        let world = &self.world;
+        let assets = &self.assets;
+        let alpha = self.alpha();
+        let camera = self.camera();
+        set_camera(&camera);
+        world
+            .target_unit
+            .draw_shadow(&assets.target_unit_shadow_texture);
+        for wall in world.walls.iter() {
+            let texture = match wall.kind {
+                WallKind::Thin => &assets.wall_thin_texture,
+                WallKind::Thick => &assets.wall_thick_texture,
+            };
+            wall.draw(texture);
+        }
+        world.main_unit.draw(&assets.main_unit_texture, alpha);
+        for unit in world.enemy_units.iter() {
+            unit.draw_shadow(&assets.enemy_unit_gray, alpha);
+            unit.draw(&assets.enemy_unit_gray, alpha);
+        }
+        for projectile in world.projectiles.iter() {
+            projectile.draw(&assets.projectile_texture, alpha);
+        }
+        self.particles.draw();
+        world.target_unit.draw(&assets.target_unit_texture);
+        hud::draw_health_bars(world, alpha);
+        self.draw_debug();
+
+        set_default_camera();
+        hud::draw(world, &self.stats);
+        if world.view != world.size {
+            hud::draw_minimap(world, camera.target, alpha);
+        }
+    }
+
+    /// Итоги попытки поверх уровня: цели и статистика.
+    pub fn draw_results(&self) {
+        let (title, hint) = match self.world.result() {
+            Some(LevelResult::Success) => ("LEVEL COMPLETE", "Enter - next level, R - restart"),
+            _ => ("LEVEL FAILED", "Enter or R - restart"),
+        };
+        let mut lines = vec![(title.to_string(), 64.)];
+        lines.extend(
+            self.world
+                .objectives
+                .lines()
+                .into_iter()
+                .map(|line| (line, 28.)),
+        );
+        lines.extend(self.stats.lines().into_iter().map(|line| (line, 28.)));
+        lines.push((hint.to_string(), 32.));
+        draw_text_lines(&lines, screen_height() * 0.3);
+    }
+
+    /// Отладочный слой: непроходимые клетки и пути enemy_units.
+    fn draw_debug(&self) {
+        if !self.debug {
+            return;
+        }
+        let world = &self.world;
+        let color = config().debug_color;
+        let mut cell_color = color;
+        cell_color.a *= 0.3;
+        for rect in world.navigation.blocked_cells() {
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, cell_color);
+        }
+        for unit in world.enemy_units.iter() {
+            let mut from = unit.position;
+            let waypoint = world.enemy_unit_waypoint(from);
+            let path = if waypoint == world.main_unit.position {
+                vec![from, waypoint]
+            } else {
+                world.navigation.path(from, NAVIGATION_DEBUG_PATH_LENGTH)
+            };
+            for to in path.into_iter().skip(1) {
+                draw_line(from.x, from.y, to.x, to.y, 2., color);
+                from = to;
+            }
+            let state = format!("{:?}", unit.state);
+            let (x, y) = (unit.position.x - unit.radius, unit.position.y - unit.radius);
+            draw_text(&state, x, y, 16., color);
+        }
+    }
+}
+
+/// Строки текста `(текст, размер)` по центру экрана, начиная с высоты `y`
+pub fn draw_text_lines(lines: &[(String, f32)], mut y: f32) {
+    for (text, size) in lines {
+        let width = measure_text(text, None, *size as u16, 1.).width;
+        draw_text(
+            text,
+            (screen_width() - width) * 0.5,
+            y,
+            *size,
+            config().screen_text_color,
+        );
+        y += size;
+    }
+}
+
+/// Прочитать запись прогона. Пустой путь - записи нет.
+async fn load_replay(path: &str) -> Option<Replay> {
+    if path.is_empty() {
+        return None;
+    }
+    let text = match load_string(path).await {
+        Ok(text) => text,
+        Err(e) => {
+            info!("replay: не удалось прочитать {}: {}", path, e);
+            return None;
         }
-        for i in 0..self.projectiles.len() {
-            self.projectiles[i].draw();
+    };
+    match Replay::parse(&text) {
+        Ok(replay) => Some(replay),
+        Err(e) => {
+            info!("{}", e);
+            None
         }
-        self.target_unit.draw();
     }
 }
@@ -1,25 +1,136 @@
-use macroquad::prelude::{Color, Conf};
+//! постоянные игры и значения по умолчанию для `Config`
+//!
+//! Во время игры настраиваемые значения берутся из `config()`, а не отсюда.
+
+use macroquad::prelude::{Color, Conf, Vec2};
 
 pub const GROUND_COLOR: Color = Color::new(0.77, 0.8, 0.8, 1.00);
 pub const UNIT_COLOR: Color = Color::new(0.94, 0.94, 0.94, 1.);
 pub const PROJECTILE_COLOR: Color = Color::new(1.00, 0.96, 0.84, 1.00);
+pub const ENEMY_PROJECTILE_COLOR: Color = Color::new(0.55, 0.27, 0.07, 1.00);
+pub const WALL_COLOR: Color = Color::new(0.9, 0.9, 0.9, 1.);
+pub const DEBUG_COLOR: Color = Color::new(0.9, 0.2, 0.2, 0.6);
+pub const HUD_COLOR: Color = Color::new(0.15, 0.15, 0.15, 1.);
+pub const HEALTH_BAR_COLOR: Color = Color::new(0.2, 0.7, 0.2, 0.9);
+pub const HEALTH_BAR_BACK_COLOR: Color = Color::new(0.1, 0.1, 0.1, 0.4);
+pub const SCREEN_SHADE_COLOR: Color = Color::new(0.77, 0.8, 0.8, 0.7);
+pub const SCREEN_TEXT_COLOR: Color = Color::new(0.6, 0.1, 0.1, 1.);
+pub const MINIMAP_COLOR: Color = Color::new(0.1, 0.1, 0.1, 0.5);
+pub const MINIMAP_ENEMY_COLOR: Color = Color::new(0.9, 0.2, 0.2, 1.);
+pub const MINIMAP_TARGET_COLOR: Color = Color::new(0.2, 0.8, 0.2, 1.);
+
+/// шаг симуляции, с
+pub const FIXED_DT: f32 = 1. / 60.;
+/// ограничение длины кадра, чтобы после зависания не догонять симуляцию бесконечно
+pub const MAX_FRAME_TIME: f32 = 0.25;
+/// зерно случайных чисел, если не задано `--seed=`
+pub const DEFAULT_SEED: u64 = 0;
+
+// громкость от 0 до 1; громкость эффекта умножается на `SFX_VOLUME` и `MASTER_VOLUME`
+pub const MASTER_VOLUME: f32 = 1.;
+pub const SFX_VOLUME: f32 = 1.;
+pub const MUSIC_VOLUME: f32 = 0.5;
+/// звук выключен при запуске, переключается клавишей M
+pub const MUTE: bool = false;
+/// сколько копий одного эффекта звучат одновременно, лишние пропускаются
+pub const SOUND_MAX_INSTANCES: usize = 3;
+/// сколько звучит копия эффекта, с; примерно длина звука выстрела
+pub const SOUND_INSTANCE_TIME: f32 = 1.2;
 
 pub const MAIN_UNIT_SPEED: f32 = 300.;
 pub const MAIN_UNIT_SHOOT_DELAY: f32 = 0.1;
 pub const MAIN_UNIT_SHOOT_RANGE: f32 = 3000.;
 pub const MAIN_UNIT_SHOOT_SOUND_VOLUME: f32 = 0.16;
+pub const MAIN_UNIT_SIZE: Vec2 = Vec2::new(42., 107.);
+/// радиус столкновения со стенами и enemy_units
+pub const MAIN_UNIT_RADIUS: f32 = 21.;
+pub const MAIN_UNIT_HIT_POINTS: f32 = 100.;
+/// неуязвимость после получения урона, с
+pub const MAIN_UNIT_INVULNERABILITY_TIME: f32 = 0.5;
+pub const MAIN_UNIT_IMPACT_SOUND_VOLUME: f32 = 0.3;
 pub const TARGET_UNIT_IMPACT_SOUND_VOLUME: f32 = 0.345;
+pub const TARGET_UNIT_RADIUS: f32 = 43.;
+/// `target_unit` зеленеет при нулевом здоровье и уничтожен при этом значении
+pub const TARGET_UNIT_DESTROYED_HIT_POINTS: f32 = -100.;
+
+/// больший размер миникарты в углу окна, px
+pub const MINIMAP_SIZE: f32 = 160.;
+/// размер точки юнита на миникарте, px
+pub const MINIMAP_UNIT_SIZE: f32 = 4.;
+
+/// число частиц в пуле, при переполнении заменяются самые старые
+pub const PARTICLES_CAPACITY: usize = 512;
+/// торможение частиц, доля скорости в секунду
+pub const PARTICLES_DRAG: f32 = 4.;
+
+pub const PROJECTILE_SIZE: Vec2 = Vec2::new(107., 124.);
+
+pub const WALL_IMPACT_SOUND_VOLUME: f32 = 0.1;
 
 pub const ENEMY_UNIT_ROTATION_SPEED: f32 = 4.0;
 pub const ENEMY_UNIT_SPEED: f32 = 130.0;
 pub const ENEMY_UNIT_IMPACT_SOUND_VOLUME: f32 = 0.08;
+pub const ENEMY_UNIT_RADIUS: f32 = 20.;
+/// урон `main_unit` при столкновении
+pub const ENEMY_UNIT_CONTACT_DAMAGE: f32 = 10.;
+
+// оружие enemy_unit, значения по умолчанию для `weapon` в `enemy_types`
+/// время между выстрелами, с
+pub const ENEMY_UNIT_SHOOT_DELAY: f32 = 1.5;
+/// дальность стрельбы и полета снаряда, px
+pub const ENEMY_UNIT_SHOOT_RANGE: f32 = 350.;
+/// точность от 0 до 1, 1 - без разброса
+pub const ENEMY_UNIT_SHOOT_ACCURACY: f32 = 0.8;
+/// разброс при нулевой точности, градусы в каждую сторону
+pub const ENEMY_UNIT_SHOOT_SPREAD: f32 = 30.;
+pub const ENEMY_UNIT_PROJECTILE_SPEED: f32 = 400.;
+/// урон `main_unit` от снаряда
+pub const ENEMY_UNIT_PROJECTILE_DAMAGE: f32 = 10.;
+pub const ENEMY_UNIT_SHOOT_SOUND_VOLUME: f32 = 0.06;
+/// разброс точки появления в волнах, чтобы группа не появлялась в одной точке, px
+pub const ENEMY_UNIT_SPAWN_SPREAD: f32 = 10.;
+/// дальность обнаружения `main_unit` по умолчанию, px
+pub const ENEMY_UNIT_SIGHT_RANGE: f32 = 300.;
+/// точка патруля считается достигнутой на этом расстоянии, px
+pub const ENEMY_UNIT_PATROL_TOLERANCE: f32 = 15.;
+
+// роевое поведение: радиусы правил, px, и их веса относительно движения к цели
+pub const ENEMY_UNIT_SEPARATION_RADIUS: f32 = 70.;
+pub const ENEMY_UNIT_SEPARATION_WEIGHT: f32 = 1.5;
+pub const ENEMY_UNIT_ALIGNMENT_RADIUS: f32 = 120.;
+pub const ENEMY_UNIT_ALIGNMENT_WEIGHT: f32 = 0.3;
+pub const ENEMY_UNIT_COHESION_RADIUS: f32 = 150.;
+pub const ENEMY_UNIT_COHESION_WEIGHT: f32 = 0.2;
+
+// очки за попытку
+pub const SCORE_TARGET_HIT: i32 = 10;
+pub const SCORE_ENEMY_KILL: i32 = 25;
+/// штраф за единицу полученного урона
+pub const SCORE_DAMAGE_PENALTY: f32 = 1.;
+pub const SCORE_CLEAR_BONUS: i32 = 500;
+
+/// размер полоски здоровья над юнитом, px
+pub const HEALTH_BAR_SIZE: Vec2 = Vec2::new(40., 5.);
+/// размер шрифта списка ошибок загрузки и файлов на экране загрузки
+pub const ERROR_FONT_SIZE: f32 = 20.;
+/// полоса прогресса на экране загрузки, px
+pub const LOADING_BAR_SIZE: Vec2 = Vec2::new(400., 20.);
+pub const HUD_FONT_SIZE: f32 = 24.;
+
+/// пауза перед волной enemy_units по умолчанию, с
+pub const WAVE_DELAY: f32 = 3.;
+
+/// размер клетки сетки поиска пути, px
+pub const NAVIGATION_CELL_SIZE: f32 = 20.;
+/// сколько точек пути показывать в отладке
+pub const NAVIGATION_DEBUG_PATH_LENGTH: usize = 40;
 
 pub fn window_conf() -> Conf {
     Conf {
         window_title: String::from("lvl_0"),
         window_width: 800,
         window_height: 800,
-        window_resizable: false,
+        window_resizable: true,
         ..Default::default()
     }
 }
@@ -0,0 +1,90 @@
+//! статистика попытки: выстрелы, попадания, урон и время
+
+use crate::config::config;
+use crate::world::Event;
+use serde::Serialize;
+
+/// Итоги одной попытки прохождения уровня.
+/// Страница получает их параметром `stats` в виде JSON.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RunStats {
+    pub shots: u32,
+    pub target_hits: u32,
+    pub enemy_hits: u32,
+    pub enemy_kills: u32,
+    /// доля попавших выстрелов от 0 до 1
+    pub accuracy: f32,
+    pub damage_taken: f32,
+    /// время попытки, с
+    pub time: f32,
+    /// время прохождения уровня, с; `None`, пока уровень не пройден
+    pub clear_time: Option<f32>,
+    pub score: i32,
+}
+
+impl RunStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Учесть шаг симуляции длиной `dt`.
+    pub fn update(&mut self, dt: f32) {
+        self.time += dt;
+    }
+
+    /// Учесть событие симуляции.
+    pub fn record(&mut self, event: &Event) {
+        match *event {
+            Event::MainUnitShoot { .. } => self.shots += 1,
+            Event::TargetUnitHit { .. } => self.target_hits += 1,
+            Event::EnemyUnitHit { killed, .. } => {
+                self.enemy_hits += 1;
+                if killed {
+                    self.enemy_kills += 1;
+                }
+            }
+            Event::MainUnitHit { damage, .. } => self.damage_taken += damage,
+            Event::LevelComplete => self.clear_time = Some(self.time),
+            _ => {}
+        }
+        self.accuracy = if self.shots > 0 {
+            (self.target_hits + self.enemy_hits) as f32 / self.shots as f32
+        } else {
+            0.
+        };
+        let config = config();
+        self.score = self.target_hits as i32 * config.score_target_hit
+            + self.enemy_kills as i32 * config.score_enemy_kill
+            - (self.damage_taken * config.score_damage_penalty) as i32
+            + if self.clear_time.is_some() {
+                config.score_clear_bonus
+            } else {
+                0
+            };
+    }
+
+    /// Итоги строками для экрана результатов.
+    pub fn lines(&self) -> Vec<String> {
+        let clear_time = match self.clear_time {
+            Some(time) => format!("{:.1} s", time),
+            None => "-".to_string(),
+        };
+        vec![
+            format!("score: {}", self.score),
+            format!("shots: {}", self.shots),
+            format!("accuracy: {:.0}%", self.accuracy * 100.),
+            format!("target hits: {}", self.target_hits),
+            format!(
+                "enemy hits: {}, kills: {}",
+                self.enemy_hits, self.enemy_kills
+            ),
+            format!("damage taken: {}", self.damage_taken),
+            format!("time: {:.1} s, clear time: {}", self.time, clear_time),
+        ]
+    }
+
+    /// Итоги в JSON для страницы.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
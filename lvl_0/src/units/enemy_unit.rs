@@ -1,96 +1,351 @@
-use crate::settings::*;
+use crate::config::config;
+use crate::utils::lerp_angle;
 use crate::Vec2;
-use macroquad::audio::Sound;
 use macroquad::color::{BLACK, GREEN};
 use macroquad::prelude::{draw_texture_ex, Color, DrawTextureParams, Texture2D, BROWN}; // FIXME
+use serde::Deserialize;
+
+/// Состояние поведения enemy_unit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EnemyState {
+    /// стоит, пока не увидит `main_unit`
+    Idle,
+    /// обходит точки патруля, пока не увидит `main_unit`
+    Patrol { waypoint: usize },
+    /// преследует `main_unit`
+    Chase,
+    /// заходит сбоку, пока далеко от `main_unit`
+    Flank,
+    /// отходит при малом здоровье
+    Retreat,
+    /// оглушен попаданием
+    Stunned { timer: f32 },
+}
+
+/// Начальное состояние из описания уровня.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum InitialState {
+    Idle,
+    Patrol,
+    #[default]
+    Chase,
+}
+
+/// Настройки поведения для типа enemy_unit.
+/// По умолчанию юнит сразу преследует и не отступает, как в lvl_0.
+/// Незаданные `sight_range` и параметры оружия берутся из `config()` во время игры.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct EnemyBehaviour {
+    pub initial_state: InitialState,
+    /// дальность, с которой замечает `main_unit` из `idle` и `patrol`;
+    /// по умолчанию `enemy_unit_sight_range`
+    pub sight_range: Option<f32>,
+    /// дальше этого расстояния заходит сбоку, 0 - не заходит
+    pub flank_distance: f32,
+    /// здоровье, при котором отступает, 0 - не отступает
+    pub retreat_hit_points: f32,
+    /// время оглушения после попадания, с
+    pub stun_time: f32,
+    /// оружие, без него юнит не стреляет
+    pub weapon: Option<EnemyWeapon>,
+}
+
+impl EnemyBehaviour {
+    pub fn sight_range(&self) -> f32 {
+        self.sight_range
+            .unwrap_or_else(|| config().enemy_unit_sight_range)
+    }
+}
+
+/// Оружие типа enemy_unit.
+/// Незаданные поля берутся из `enemy_unit_shoot_*` и `enemy_unit_projectile_speed`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct EnemyWeapon {
+    /// время между выстрелами, с
+    pub cooldown: Option<f32>,
+    /// дальность стрельбы и полета снаряда, px
+    pub range: Option<f32>,
+    /// точность от 0 до 1, 1 - без разброса
+    pub accuracy: Option<f32>,
+    pub projectile_speed: Option<f32>,
+}
+
+impl EnemyWeapon {
+    pub fn cooldown(&self) -> f32 {
+        self.cooldown
+            .unwrap_or_else(|| config().enemy_unit_shoot_delay)
+    }
+
+    pub fn range(&self) -> f32 {
+        self.range
+            .unwrap_or_else(|| config().enemy_unit_shoot_range)
+    }
+
+    pub fn accuracy(&self) -> f32 {
+        self.accuracy
+            .unwrap_or_else(|| config().enemy_unit_shoot_accuracy)
+    }
+
+    pub fn projectile_speed(&self) -> f32 {
+        self.projectile_speed
+            .unwrap_or_else(|| config().enemy_unit_projectile_speed)
+    }
+}
 
 #[derive(Clone)]
 pub struct EnemyUnit {
-    pub texture: Texture2D,
-    pub shadow_texture: Texture2D,
     color: Color,
     pub position: Vec2,
     pub rotation: f32,
+    previous_position: Vec2,
+    previous_rotation: f32,
     pub radius: f32,
     pub shift: Vec2,
-    pub impact_sound: Sound,
     pub(crate) hit_points: f32,
+    pub(crate) max_hit_points: f32,
     pub(crate) alive: bool,
+    speed: f32,
+    rotation_speed: f32,
+    pub(crate) state: EnemyState,
+    behaviour: EnemyBehaviour,
+    patrol: Vec<Vec2>,
+    flank_side: f32, // с какой стороны заходить: -1 или 1
+    shoot_timer: f32,
 }
 
 impl EnemyUnit {
-    pub fn new(
-        texture: Texture2D,
-        shadow_texture: Texture2D,
-        impact_sound: Sound,
-        spawn_position: Vec2,
-    ) -> Self {
+    pub fn new(spawn_position: Vec2, hit_points: f32, speed: f32, rotation_speed: f32) -> Self {
         let color = BLACK;
+        let behaviour = EnemyBehaviour::default();
 
         Self {
-            texture: texture.clone(),
-            shadow_texture,
             color,
             position: spawn_position,
             rotation: f32::to_radians(-90.0),
-            radius: texture.width() * 0.5,
+            previous_position: spawn_position,
+            previous_rotation: f32::to_radians(-90.0),
+            radius: config().enemy_unit_radius,
             shift: Vec2::new(0., 0.),
-            impact_sound,
-            hit_points: 100.,
+            hit_points,
+            max_hit_points: hit_points,
             alive: true,
+            speed,
+            rotation_speed,
+            state: EnemyState::Chase,
+            behaviour,
+            patrol: vec![],
+            flank_side: 1.,
+            shoot_timer: 0.,
+        }
+    }
+
+    /// Задать поведение.
+    ///
+    /// ### Аргументы
+    ///
+    /// * `behaviour` - настройки типа юнита.
+    /// * `patrol` - точки патруля.
+    /// * `flank_side` - сторона обхода, -1 или 1.
+    pub fn with_behaviour(
+        mut self,
+        behaviour: EnemyBehaviour,
+        patrol: Vec<Vec2>,
+        flank_side: f32,
+    ) -> Self {
+        self.state = match behaviour.initial_state {
+            InitialState::Idle => EnemyState::Idle,
+            InitialState::Patrol if !patrol.is_empty() => EnemyState::Patrol { waypoint: 0 },
+            InitialState::Patrol => EnemyState::Idle,
+            InitialState::Chase => EnemyState::Chase,
+        };
+        self.behaviour = behaviour;
+        self.patrol = patrol;
+        self.flank_side = flank_side;
+        self
+    }
+
+    /// Попадание снаряда: оглушение, если оно настроено.
+    pub fn stun(&mut self) {
+        if self.behaviour.stun_time > 0. && self.state != EnemyState::Retreat {
+            self.state = EnemyState::Stunned {
+                timer: self.behaviour.stun_time,
+            };
+        }
+    }
+
+    /// Переходы между состояниями.
+    ///
+    /// ### Аргументы
+    ///
+    /// * `dt` - шаг времени.
+    /// * `player` - положение `main_unit`.
+    pub fn update_state(&mut self, dt: f32, player: Vec2) {
+        let distance = self.position.distance(player);
+        let behaviour = &self.behaviour;
+
+        if let EnemyState::Stunned { timer } = self.state {
+            let timer = timer - dt;
+            self.state = if timer > 0. {
+                EnemyState::Stunned { timer }
+            } else {
+                EnemyState::Chase
+            };
+            return;
+        }
+
+        if behaviour.retreat_hit_points > 0. && self.hit_points <= behaviour.retreat_hit_points {
+            self.state = EnemyState::Retreat;
+            return;
+        }
+
+        self.state = match self.state {
+            EnemyState::Idle | EnemyState::Patrol { .. } if distance < behaviour.sight_range() => {
+                EnemyState::Chase
+            }
+            EnemyState::Patrol { waypoint } => {
+                if self.position.distance(self.patrol[waypoint])
+                    < config().enemy_unit_patrol_tolerance
+                {
+                    EnemyState::Patrol {
+                        waypoint: (waypoint + 1) % self.patrol.len(),
+                    }
+                } else {
+                    EnemyState::Patrol { waypoint }
+                }
+            }
+            EnemyState::Chase | EnemyState::Flank if behaviour.flank_distance > 0. => {
+                if distance > behaviour.flank_distance {
+                    EnemyState::Flank
+                } else {
+                    EnemyState::Chase
+                }
+            }
+            state => state,
+        };
+    }
+
+    /// Перезарядка оружия и решение о выстреле.
+    /// Стреляет, только когда преследует или отступает и `main_unit` в пределах дальности.
+    ///
+    /// ### Аргументы
+    ///
+    /// * `dt` - шаг времени.
+    /// * `player` - положение `main_unit`.
+    ///
+    /// ### Возвращаемое значение
+    ///
+    /// Оружие, из которого нужно выстрелить на этом шаге.
+    pub fn update_weapon(&mut self, dt: f32, player: Vec2) -> Option<&EnemyWeapon> {
+        let weapon = self.behaviour.weapon.as_ref()?;
+        self.shoot_timer += dt;
+        let engaged = matches!(
+            self.state,
+            EnemyState::Chase | EnemyState::Flank | EnemyState::Retreat
+        );
+        if !engaged
+            || self.shoot_timer < weapon.cooldown()
+            || self.position.distance(player) > weapon.range()
+        {
+            return None;
         }
+        self.shoot_timer = 0.;
+        Some(weapon)
+    }
+
+    /// Точка, к которой двигаться в текущем состоянии.
+    /// `None` - стоять на месте.
+    ///
+    /// ### Аргументы
+    ///
+    /// * `player` - положение `main_unit`.
+    /// * `waypoint` - следующая точка пути к `main_unit` в обход стен.
+    pub fn steering_target(&self, player: Vec2, waypoint: Vec2) -> Option<Vec2> {
+        match self.state {
+            EnemyState::Idle | EnemyState::Stunned { .. } => None,
+            EnemyState::Patrol { waypoint } => Some(self.patrol[waypoint]),
+            EnemyState::Chase => Some(waypoint),
+            EnemyState::Flank => {
+                let direction = (player - self.position).normalize_or_zero();
+                let side = Vec2::new(-direction.y, direction.x) * self.flank_side;
+                Some(player + side * self.behaviour.flank_distance * 0.8)
+            }
+            EnemyState::Retreat => {
+                if self.position.distance(player) > self.behaviour.sight_range() {
+                    None
+                } else {
+                    Some(self.position * 2. - player)
+                }
+            }
+        }
+    }
+
+    /// Интерполированные положение и угол для отрисовки.
+    pub(crate) fn interpolated(&self, alpha: f32) -> (Vec2, f32) {
+        (
+            self.previous_position.lerp(self.position, alpha),
+            lerp_angle(self.previous_rotation, self.rotation, alpha),
+        )
     }
 
-    pub fn draw(&self) {
+    pub fn draw(&self, texture: &Texture2D, alpha: f32) {
         let color = if self.alive { BROWN } else { GREEN };
+        let (position, rotation) = self.interpolated(alpha);
 
         draw_texture_ex(
-            &self.texture,
-            self.position.x - self.texture.width() * 0.5 + self.shift.x,
-            self.position.y - self.texture.height() * 0.5 - self.shift.y,
+            texture,
+            position.x - texture.width() * 0.5 + self.shift.x,
+            position.y - texture.height() * 0.5 - self.shift.y,
             color,
             DrawTextureParams {
-                rotation: self.rotation - f32::to_radians(90.),
+                rotation: rotation - f32::to_radians(90.),
                 ..Default::default()
             },
         );
     }
 
-    pub fn draw_shadow(&self) {
+    pub fn draw_shadow(&self, texture: &Texture2D, alpha: f32) {
         // тень
         let height = 1.6;
         let mut color = self.color;
         color.a = 0.2;
+        let (position, rotation) = self.interpolated(alpha);
         draw_texture_ex(
-            &self.texture,
-            self.position.x - self.texture.width() * 0.5 + 3. * height,
-            self.position.y - self.texture.height() * 0.5 + 4. * height,
+            texture,
+            position.x - texture.width() * 0.5 + 3. * height,
+            position.y - texture.height() * 0.5 + 4. * height,
             color,
             DrawTextureParams {
-                rotation: self.rotation - f32::to_radians(90.),
+                rotation: rotation - f32::to_radians(90.),
                 ..Default::default()
             },
         );
     }
 
-    pub fn update(&mut self, dt: f32, target: Vec2, units: Vec<EnemyUnit>, exclude: usize) {
-        self.rotation %= f32::to_radians(360.);
-        let mut dx = self.position.x - target.x;
-        if dx == 0f32 {
-            dx += 1f32;
+    /// Движение к `target` с учетом соседей по стае.
+    ///
+    /// ### Аргументы
+    ///
+    /// * `dt` - шаг времени.
+    /// * `target` - точка назначения, `None` - стоять на месте.
+    /// * `units` - все enemy_units.
+    /// * `exclude` - номер этого юнита в `units`.
+    pub fn update(&mut self, dt: f32, target: Option<Vec2>, units: &[EnemyUnit], exclude: usize) {
+        self.previous_position = self.position;
+        self.previous_rotation = self.rotation;
+        let Some(target) = target else {
+            return;
         };
+        self.rotation %= f32::to_radians(360.);
 
-        let mut dy = self.position.y - target.y;
-        if dy == 0f32 {
-            dy += 1f32;
-        };
+        // желаемое направление движения: к цели и по правилам стаи
+        let seek = (target - self.position).normalize_or_zero();
+        let desired = seek + self.swarm_behaviour(units, exclude);
 
-        // абсолютный угол к целиwww
-        let a: f32 = if dx >= 0. {
-            (dy / dx).atan()
-        } else {
-            (dy / dx).atan() - f32::to_radians(180.)
-        };
+        // абсолютный угол; юнит движется против направления `rotation`
+        let a = (-desired.y).atan2(-desired.x);
 
         // относительный угол
         let mut da = self.rotation - a;
@@ -106,52 +361,80 @@ impl EnemyUnit {
         // сохранение направления движения
         if da.abs() > f32::to_radians(9.) {
             if da > 0. {
-                self.rotation -= dt * ENEMY_UNIT_ROTATION_SPEED
+                self.rotation -= dt * self.rotation_speed
             } else {
-                self.rotation += dt * ENEMY_UNIT_ROTATION_SPEED
+                self.rotation += dt * self.rotation_speed
             }
         }
 
-        self.swarm_behaviour(dt, units, exclude);
+        self.position.x -= dt * self.speed * self.rotation.cos();
+        self.position.y -= dt * self.speed * self.rotation.sin();
+    }
 
-        self.position.x += -1. * dt * ENEMY_UNIT_SPEED * self.rotation.cos();
-        self.position.y += -1. * dt * ENEMY_UNIT_SPEED * self.rotation.sin();
+    /// Отразить направление движения от стены.
+    ///
+    /// ### Аргументы
+    ///
+    /// * `normal` - нормаль стены в точке касания.
+    pub fn deflect(&mut self, normal: Vec2) {
+        let direction = self.heading();
+        let dot = direction.dot(normal);
+        if dot < 0. {
+            let reflected = direction - 2. * dot * normal;
+            self.rotation = (-reflected.y).atan2(-reflected.x);
+        }
     }
 
-    /// Роевое поведение
-    fn swarm_behaviour(&mut self, dt: f32, units: Vec<EnemyUnit>, exclude: usize) {
-        // отворот от близкого юнита
+    /// Направление движения юнита.
+    fn heading(&self) -> Vec2 {
+        -Vec2::new(self.rotation.cos(), self.rotation.sin())
+    }
+
+    /// Роевое поведение (boids): разделение, выравнивание и сплочение.
+    /// Радиусы и веса правил - настройки `enemy_unit_separation_*`,
+    /// `enemy_unit_alignment_*` и `enemy_unit_cohesion_*` в `config()`.
+    ///
+    /// ### Возвращаемое значение
+    ///
+    /// Поправка к желаемому направлению движения.
+    fn swarm_behaviour(&self, units: &[EnemyUnit], exclude: usize) -> Vec2 {
+        let config = config();
+        let mut separation = Vec2::ZERO;
+        let mut alignment = Vec2::ZERO;
+        let mut center = Vec2::ZERO;
+        let mut neighbours = 0;
+
         for (i, unit) in units.iter().enumerate() {
             if i == exclude {
                 continue;
             }
-            let x0 = self.position.x;
-            let y0 = self.position.y;
-            let x1 = unit.position.x;
-            let y1 = unit.position.y;
-            let dx = x0 - x1;
-            let dy = y0 - y1;
-            let distance = (dx.powf(2.) + dy.powf(2.)).sqrt();
-            if distance < 70. {
-                // соседний юнит близко
-
-                // абсолютный угол
-                let a: f32 = if dx >= 0. {
-                    (dy / dx).atan()
-                } else {
-                    (dy / dx).atan() - f32::to_radians(180.)
-                };
-
-                // относительный угол
-                let da = self.rotation - a;
+            let offset = self.position - unit.position;
+            let distance = offset.length();
 
-                // отворачивать от близкого юнита
-                if da < 0. && da > -20. {
-                    self.rotation -= 0.7 * dt * ENEMY_UNIT_ROTATION_SPEED
-                } else if da > 0. && da < 20. {
-                    self.rotation += 0.7 * dt * ENEMY_UNIT_ROTATION_SPEED
-                }
+            // отворот от близкого юнита, тем сильнее, чем ближе
+            if distance < config.enemy_unit_separation_radius && distance > 0. {
+                separation +=
+                    offset / distance * (1. - distance / config.enemy_unit_separation_radius);
+            }
+            // общее направление с соседями
+            if distance < config.enemy_unit_alignment_radius {
+                alignment += unit.heading();
+            }
+            // притяжение к центру группы
+            if distance < config.enemy_unit_cohesion_radius {
+                center += unit.position;
+                neighbours += 1;
             }
         }
+
+        let cohesion = if neighbours > 0 {
+            (center / neighbours as f32 - self.position).normalize_or_zero()
+        } else {
+            Vec2::ZERO
+        };
+
+        separation * config.enemy_unit_separation_weight
+            + alignment.normalize_or_zero() * config.enemy_unit_alignment_weight
+            + cohesion * config.enemy_unit_cohesion_weight
     }
 }
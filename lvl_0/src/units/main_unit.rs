@@ -1,21 +1,26 @@
 use crate::command::Command;
+use crate::config::config;
 use crate::settings::*;
+use crate::utils::lerp_angle;
 use macroquad::prelude::*;
 
 /// Основной юнит, под контролем игрока.
 pub struct MainUnit {
-    pub texture: Texture2D,
     pub size: Vec2,
     pub _scale: f32,
     pub _radius: f32,
     pub rotation: f32,
     pub position: Vec2,
+    previous_rotation: f32,
+    previous_position: Vec2,
     pub speed: f32,
     pub shoot_timer: f32,
     shoot_delay: f32,
     pub shoot_range: f32,
-    pub auto_aim: bool,
-    bullet_load: u8,
+    pub hit_points: f32,
+    pub max_hit_points: f32,
+    pub alive: bool,
+    damage_timer: f32, // оставшееся время неуязвимости
 }
 
 impl MainUnit {
@@ -23,26 +28,38 @@ impl MainUnit {
     ///
     /// ### Аргументы
     ///
-    /// * `texture` - текстура для отрисовки юнита.
     /// * `position` - начальное положение юнита.
+    /// * `speed` - скорость движения.
+    /// * `shoot_delay` - задержка между выстрелами.
+    /// * `shoot_range` - дальность полета снаряда.
+    /// * `hit_points` - здоровье.
     ///
     /// ### Возвращаемое значение
     ///
     /// Возвращает новый экземпляр структуры MainUnit.
-    pub fn new(texture: Texture2D, position: Vec2) -> Self {
+    pub fn new(
+        position: Vec2,
+        speed: f32,
+        shoot_delay: f32,
+        shoot_range: f32,
+        hit_points: f32,
+    ) -> Self {
         Self {
-            texture: texture.clone(),
             position,
-            size: Vec2::new(texture.width(), texture.height()),
+            previous_rotation: 0.,
+            previous_position: position,
+            size: MAIN_UNIT_SIZE,
             _scale: 1.,
-            _radius: f32::max(texture.width(), texture.height()),
+            _radius: f32::max(MAIN_UNIT_SIZE.x, MAIN_UNIT_SIZE.y),
             rotation: 0.,
-            speed: MAIN_UNIT_SPEED,
+            speed,
             shoot_timer: 0.,
-            shoot_delay: MAIN_UNIT_SHOOT_DELAY,
-            shoot_range: MAIN_UNIT_SHOOT_RANGE,
-            auto_aim: false,
-            bullet_load: 0,
+            shoot_delay,
+            shoot_range,
+            hit_points,
+            max_hit_points: hit_points,
+            alive: true,
+            damage_timer: 0.,
         }
     }
 
@@ -51,47 +68,101 @@ impl MainUnit {
     /// ### Аргументы
     ///
     /// * `dt` - шаг времени.
-    /// * `target_point` - позиция цели.
     /// * `command` - команда для управления юнитом.
-    pub fn update(&mut self, dt: f32, target_point: Vec2, command: &mut Command) {
+    /// * `bounds` - размер поля, за которое юнит не выходит.
+    ///
+    /// ### Возвращаемое значение
+    ///
+    /// `true`, если на этом шаге произошел выстрел.
+    pub fn update(&mut self, dt: f32, command: &Command, bounds: Vec2) -> bool {
+        self.previous_position = self.position;
+        self.previous_rotation = self.rotation;
         self.shoot_timer += dt;
-        self.update_position(dt, command);
-        self.update_rotation(target_point, command);
-        self.update_shooting(command);
+        self.damage_timer = (self.damage_timer - dt).max(0.);
+        self.update_position(dt, command, bounds);
+        self.update_rotation(command);
+        self.update_shooting(command)
+    }
+
+    /// Получить урон.
+    /// После урона юнит на `MAIN_UNIT_INVULNERABILITY_TIME` неуязвим,
+    /// поэтому долгое касание не отнимает здоровье каждый шаг.
+    ///
+    /// ### Аргументы
+    ///
+    /// * `damage` - величина урона.
+    ///
+    /// ### Возвращаемое значение
+    ///
+    /// `true`, если урон прошел.
+    pub fn hit(&mut self, damage: f32) -> bool {
+        if !self.alive || self.damage_timer > 0. {
+            return false;
+        }
+        self.hit_points -= damage;
+        self.damage_timer = config().main_unit_invulnerability_time;
+        if self.hit_points <= 0. {
+            self.hit_points = 0.;
+            self.alive = false;
+        }
+        true
+    }
+
+    /// Положение для отрисовки между прошлым и текущим шагом.
+    pub fn interpolated_position(&self, alpha: f32) -> Vec2 {
+        self.previous_position.lerp(self.position, alpha)
     }
 
     /// Отрисовывает юнит.
-    pub fn draw(&self) {
-        self.draw_shadow();
-        self.draw_main_unit();
+    ///
+    /// ### Аргументы
+    ///
+    /// * `texture` - текстура юнита.
+    /// * `alpha` - доля шага симуляции между прошлым и текущим состоянием.
+    pub fn draw(&self, texture: &Texture2D, alpha: f32) {
+        let position = self.interpolated_position(alpha);
+        let rotation = lerp_angle(self.previous_rotation, self.rotation, alpha);
+        self.draw_shadow(texture, position, rotation);
+        self.draw_main_unit(texture, position, rotation);
     }
 
     /// Обновляет позицию юнита.
-    fn update_position(&mut self, dt: f32, command: &Command) {
-        self.position.x += command.wasd.x * dt * self.speed;
-        self.position.y += command.wasd.y * dt * self.speed;
-
-        if command.wasd.x != 0. || command.wasd.y != 0. || is_mouse_button_down(MouseButton::Left) {
-            self.auto_aim = false;
+    /// У края поля юнит выталкивается обратно.
+    fn update_position(&mut self, dt: f32, command: &Command, bounds: Vec2) {
+        let mut wasd = command.wasd;
+        if self.position.x < 1f32 {
+            wasd.x = 1f32;
+        }
+        if self.position.x > bounds.x {
+            wasd.x = -1f32;
+        }
+        if self.position.y < 1f32 {
+            wasd.y = 1f32;
         }
+        if self.position.y > bounds.y {
+            wasd.y = -1f32;
+        }
+
+        self.position.x += wasd.x * dt * self.speed;
+        self.position.y += wasd.y * dt * self.speed;
     }
 
     /// Обновляет угол поворота юнита.
-    fn update_rotation(&mut self, target_point: Vec2, command: &Command) {
+    fn update_rotation(&mut self, command: &Command) {
         self.rotation %= f32::to_radians(360.);
-        let mut dx = self.position.x - target_point.x;
+        let mut dx = self.position.x - command.target_point.x;
         if dx == 0f32 {
             dx += 1f32;
         };
 
-        let mut dy = self.position.y - target_point.y;
+        let mut dy = self.position.y - command.target_point.y;
         if dy == 0f32 {
             dy += 1f32;
         };
 
-        if self.auto_aim {
+        if command.auto_aim {
             self.rotation = command.rotation;
-        } else if !self.auto_aim {
+        } else {
             if dx >= 0f32 {
                 self.rotation = (dy / dx).atan() - f32::to_radians(90.);
             } else {
@@ -101,49 +172,39 @@ impl MainUnit {
     }
 
     /// Обновляет стрельбу юнита.
-    fn update_shooting(&mut self, command: &mut Command) {
-        if self.shoot_timer >= self.shoot_delay {
-            if is_mouse_button_down(MouseButton::Left) {
-                command.shoot = true;
-                self.bullet_load = 0;
-            } else if self.bullet_load > 0 {
-                command.shoot = true;
-                self.bullet_load -= 1;
-            }
-        } else {
-            command.shoot = false;
-        }
-
-        if command.shoot {
+    fn update_shooting(&mut self, command: &Command) -> bool {
+        if command.shoot && self.shoot_timer >= self.shoot_delay {
             self.shoot_timer = 0.;
+            return true;
         }
+        false
     }
 
     /// Отрисовывает тень юнита.
-    fn draw_shadow(&self) {
+    fn draw_shadow(&self, texture: &Texture2D, position: Vec2, rotation: f32) {
         draw_texture_ex(
-            &self.texture,
-            self.position.x - self.size.x * 0.5 + 3.,
-            self.position.y - self.size.y * 0.5 + 4.,
+            texture,
+            position.x - self.size.x * 0.5 + 3.,
+            position.y - self.size.y * 0.5 + 4.,
             DARKGRAY,
             DrawTextureParams {
                 dest_size: Some(Vec2::new(self.size.x, self.size.y)),
-                rotation: self.rotation,
+                rotation,
                 ..Default::default()
             },
         );
     }
 
     /// Отрисовывает главный объект юнита.
-    fn draw_main_unit(&self) {
+    fn draw_main_unit(&self, texture: &Texture2D, position: Vec2, rotation: f32) {
         draw_texture_ex(
-            &self.texture,
-            self.position.x - self.size.x * 0.5,
-            self.position.y - self.size.y * 0.5,
-            UNIT_COLOR,
+            texture,
+            position.x - self.size.x * 0.5,
+            position.y - self.size.y * 0.5,
+            config().unit_color,
             DrawTextureParams {
                 dest_size: Some(Vec2::new(self.size.x, self.size.y)),
-                rotation: self.rotation,
+                rotation,
                 ..Default::default()
             },
         );
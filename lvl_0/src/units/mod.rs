@@ -4,3 +4,4 @@ pub(crate) mod enemy_unit;
 pub(crate) mod main_unit;
 pub(crate) mod projectile;
 pub(crate) mod target_unit;
+pub(crate) mod wall;
@@ -1,16 +1,24 @@
+use crate::config::config;
 use crate::settings::*;
-use macroquad::audio;
-use macroquad::audio::{PlaySoundParams, Sound};
 use macroquad::prelude::*;
 
-/// Проектайл (снаряд), выпущенный игроком.
+/// Чей снаряд: свои снаряды не поражают своих.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Team {
+    Player,
+    Enemy,
+}
+
+/// Проектайл (снаряд), выпущенный игроком или enemy_unit.
 pub struct Projectile {
-    pub texture: Texture2D,
     pub rotation: f32,
     pub start_position: Vec2,
     pub position: Vec2,
+    pub previous_position: Vec2,
     pub size: Vec2,
     pub speed: f32,
+    pub range: f32, // дальность полета
+    pub team: Team,
     pub alive: bool,
 }
 
@@ -19,34 +27,25 @@ impl Projectile {
     ///
     /// ### Аргументы
     ///
-    /// * `texture` - текстура проектайла.
-    /// * `shoot_sound` - звук выстрела.
     /// * `rotation` - угол поворота проектайла.
     /// * `position` - начальная позиция проектайла.
     /// * `speed` - скорость проектайла.
+    /// * `range` - дальность полета.
+    /// * `team` - кто выстрелил.
     ///
     /// ### Возвращаемое значение
     ///
     /// Возвращает новый экземпляр структуры Projectile.
-    pub fn new(
-        texture: Texture2D,
-        shoot_sound: Sound,
-        rotation: f32,
-        position: Vec2,
-        speed: f32,
-    ) -> Self {
-        audio::play_sound_once(
-            &shoot_sound,
-        );
-
-        let size = Vec2::new(texture.width(), texture.height());
+    pub fn new(rotation: f32, position: Vec2, speed: f32, range: f32, team: Team) -> Self {
         Self {
-            texture,
             rotation,
             start_position: position,
             position,
-            size,
+            previous_position: position,
+            size: PROJECTILE_SIZE,
             speed,
+            range,
+            team,
             alive: true,
         }
     }
@@ -61,15 +60,15 @@ impl Projectile {
         self.position.y += dt * self.speed * (self.rotation - f32::to_radians(90.)).sin();
     }
 
-    /// Проверяет, находится ли проектайл в пределах экрана.
-    pub fn is_within_screen_bounds(&self) -> bool {
+    /// Проверяет, находится ли проектайл в пределах поля.
+    pub fn is_within_bounds(&self, bounds: Vec2) -> bool {
         let half_width = self.size.x * 0.5;
         let half_height = self.size.y * 0.5;
 
         self.position.x + half_width >= 0.
-            && self.position.x - half_width <= screen_width()
+            && self.position.x - half_width <= bounds.x
             && self.position.y + half_height >= 0.
-            && self.position.y - half_height <= screen_height()
+            && self.position.y - half_height <= bounds.y
     }
 
     /// Обновляет состояние проектайла.
@@ -77,18 +76,30 @@ impl Projectile {
     /// ### Аргументы
     ///
     /// * `dt` - шаг времени.
-    pub fn update(&mut self, dt: f32) {
+    /// * `bounds` - размер поля.
+    pub fn update(&mut self, dt: f32, bounds: Vec2) {
+        self.previous_position = self.position;
         self.update_position(dt);
-        self.alive = self.alive && self.is_within_screen_bounds();
+        self.alive = self.alive && self.is_within_bounds(bounds);
     }
 
     /// Отрисовывает проектайл.
-    pub fn draw(&self) {
+    ///
+    /// ### Аргументы
+    ///
+    /// * `texture` - текстура проектайла.
+    /// * `alpha` - доля шага симуляции между прошлым и текущим состоянием.
+    pub fn draw(&self, texture: &Texture2D, alpha: f32) {
+        let position = self.previous_position.lerp(self.position, alpha);
+        let color = match self.team {
+            Team::Player => config().projectile_color,
+            Team::Enemy => config().enemy_projectile_color,
+        };
         draw_texture_ex(
-            &self.texture,
-            self.position.x - self.size.x * 0.50,
-            self.position.y - self.size.y * 0.50,
-            PROJECTILE_COLOR,
+            texture,
+            position.x - self.size.x * 0.50,
+            position.y - self.size.y * 0.50,
+            color,
             DrawTextureParams {
                 dest_size: Some(Vec2::new(self.size.x, self.size.y)),
                 rotation: self.rotation,
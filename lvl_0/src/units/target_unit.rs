@@ -1,19 +1,16 @@
-use crate::{Vec2, TARGET_UNIT_IMPACT_SOUND_VOLUME};
-use macroquad::audio;
-use macroquad::audio::{PlaySoundParams, Sound};
+use crate::config::config;
+use crate::Vec2;
 use macroquad::color::{BLACK, GREEN, WHITE};
 use macroquad::prelude::{draw_texture_ex, Color, DrawTextureParams, Texture2D};
 
 /// Цель, которую игрок должен поражать.
 pub struct TargetUnit {
-    pub texture: Texture2D,
-    pub shadow_texture: Texture2D,
     color: Color,
     pub position: Vec2,
     pub radius: f32,
     pub shift: Vec2,
-    impact_sound: Sound,
     pub(crate) hit_points: f32,
+    pub(crate) max_hit_points: f32,
     pub alive: bool,
 }
 
@@ -22,32 +19,23 @@ impl TargetUnit {
     ///
     /// ### Аргументы
     ///
-    /// * `texture` - текстура для отрисовки цели.
-    /// * `shadow_texture` - текстура для тени цели.
-    /// * `impact_sound` - звук столкновения.
     /// * `spawn_position` - начальное положение цели.
+    /// * `hit_points` - начальное здоровье цели.
     ///
     /// ### Возвращаемое значение
     ///
     /// Возвращает новый экземпляр структуры TargetUnit.
-    pub fn new(
-        texture: Texture2D,
-        shadow_texture: Texture2D,
-        impact_sound: Sound,
-        spawn_position: Vec2,
-    ) -> Self {
+    pub fn new(spawn_position: Vec2, hit_points: f32) -> Self {
         let mut color = BLACK;
         color.a = 0.45;
 
         Self {
-            texture: texture.clone(),
-            shadow_texture,
             color,
             position: spawn_position,
-            radius: texture.width() * 0.5,
+            radius: config().target_unit_radius,
             shift: Vec2::new(0., 0.),
-            impact_sound,
-            hit_points: 100.,
+            hit_points,
+            max_hit_points: hit_points,
             alive: true,
         }
     }
@@ -69,17 +57,6 @@ impl TargetUnit {
 
             let shift = 5.;
             self.shift = Vec2::new(shift * impact_angle.sin(), shift * impact_angle.cos());
-
-            let volume = if self.alive {
-                TARGET_UNIT_IMPACT_SOUND_VOLUME
-            } else {
-                TARGET_UNIT_IMPACT_SOUND_VOLUME * 0.25
-            };
-
-            // Проигрывает звук столкновения
-            audio::play_sound_once(
-                &self.impact_sound,
-            );
         }
     }
 
@@ -96,13 +73,13 @@ impl TargetUnit {
     }
 
     /// Отрисовывает цель.
-    pub fn draw(&self) {
+    pub fn draw(&self, texture: &Texture2D) {
         let color = if self.alive { WHITE } else { GREEN };
 
         draw_texture_ex(
-            &self.texture,
-            self.position.x - self.texture.width() * 0.5 + self.shift.x,
-            self.position.y - self.texture.height() * 0.5 - self.shift.y,
+            texture,
+            self.position.x - texture.width() * 0.5 + self.shift.x,
+            self.position.y - texture.height() * 0.5 - self.shift.y,
             color,
             DrawTextureParams {
                 ..Default::default()
@@ -111,14 +88,14 @@ impl TargetUnit {
     }
 
     /// Отрисовывает тень цели.
-    pub fn draw_shadow(&self) {
+    pub fn draw_shadow(&self, shadow_texture: &Texture2D) {
         // Вычисление параметра height для тени
         let height = 3.;
 
         draw_texture_ex(
-            &self.shadow_texture,
-            self.position.x - self.texture.width() * 0.5 + 3. * height,
-            self.position.y - self.texture.height() * 0.5 + 4. * height,
+            shadow_texture,
+            self.position.x - self.radius + 3. * height,
+            self.position.y - self.radius + 4. * height,
             self.color,
             DrawTextureParams {
                 ..Default::default()
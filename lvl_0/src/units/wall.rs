@@ -0,0 +1,151 @@
+use crate::config::config;
+use macroquad::prelude::*;
+use serde::Deserialize;
+
+/// Вид стены, определяет текстуру.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WallKind {
+    /// `wall_50x15.png`
+    Thin,
+    /// `wall_50x20.png`
+    #[default]
+    Thick,
+}
+
+/// Стена - прямоугольное препятствие.
+/// Останавливает юнитов и снаряды.
+pub struct Wall {
+    pub position: Vec2, // центр
+    pub size: Vec2,
+    pub kind: WallKind,
+}
+
+impl Wall {
+    /// Создает новую стену.
+    ///
+    /// ### Аргументы
+    ///
+    /// * `position` - центр стены.
+    /// * `size` - ширина и высота стены.
+    /// * `kind` - вид стены.
+    pub fn new(position: Vec2, size: Vec2, kind: WallKind) -> Self {
+        Self {
+            position,
+            size,
+            kind,
+        }
+    }
+
+    /// Прямоугольник стены.
+    pub fn rect(&self) -> Rect {
+        Rect::new(
+            self.position.x - self.size.x * 0.5,
+            self.position.y - self.size.y * 0.5,
+            self.size.x,
+            self.size.y,
+        )
+    }
+
+    /// Пересечение отрезка со стеной.
+    ///
+    /// ### Аргументы
+    ///
+    /// * `a` - начало отрезка.
+    /// * `b` - конец отрезка.
+    ///
+    /// ### Возвращаемое значение
+    ///
+    /// Первая точка отрезка внутри стены или `None`.
+    pub fn segment_hit(&self, a: Vec2, b: Vec2) -> Option<Vec2> {
+        let rect = self.rect();
+        let min = rect.point();
+        let max = min + rect.size();
+        let d = b - a;
+
+        let mut t0 = 0f32;
+        let mut t1 = 1f32;
+        for (p, dp, lo, hi) in [(a.x, d.x, min.x, max.x), (a.y, d.y, min.y, max.y)] {
+            if dp == 0. {
+                if p < lo || p > hi {
+                    return None;
+                }
+                continue;
+            }
+            let ta = (lo - p) / dp;
+            let tb = (hi - p) / dp;
+            t0 = t0.max(ta.min(tb));
+            t1 = t1.min(ta.max(tb));
+            if t0 > t1 {
+                return None;
+            }
+        }
+        Some(a + d * t0)
+    }
+
+    /// Выталкивает круг из стены.
+    ///
+    /// ### Аргументы
+    ///
+    /// * `position` - центр круга, исправляется на месте.
+    /// * `radius` - радиус круга.
+    ///
+    /// ### Возвращаемое значение
+    ///
+    /// Нормаль стены в точке касания, если круг задел стену.
+    pub fn push_out(&self, position: &mut Vec2, radius: f32) -> Option<Vec2> {
+        let rect = self.rect();
+        let closest = position.clamp(rect.point(), rect.point() + rect.size());
+        let d = *position - closest;
+
+        if d.length_squared() >= radius * radius {
+            return None;
+        }
+
+        let normal = if d.length_squared() > 0. {
+            d.normalize()
+        } else {
+            // центр внутри стены: выталкивать по ближайшей стороне
+            let to_min = *position - rect.point();
+            let to_max = rect.point() + rect.size() - *position;
+            let sides = [
+                (to_min.x, Vec2::new(-1., 0.)),
+                (to_max.x, Vec2::new(1., 0.)),
+                (to_min.y, Vec2::new(0., -1.)),
+                (to_max.y, Vec2::new(0., 1.)),
+            ];
+            let (depth, normal) = sides
+                .into_iter()
+                .min_by(|a, b| a.0.total_cmp(&b.0))
+                .unwrap();
+            *position += normal * depth;
+            normal
+        };
+
+        let closest = position.clamp(rect.point(), rect.point() + rect.size());
+        *position = closest + normal * radius;
+        Some(normal)
+    }
+
+    /// Отрисовывает стену.
+    /// Текстура горизонтальная, для вертикальной стены поворачивается.
+    pub fn draw(&self, texture: &Texture2D) {
+        let vertical = self.size.y > self.size.x;
+        let dest_size = if vertical {
+            Vec2::new(self.size.y, self.size.x)
+        } else {
+            self.size
+        };
+        draw_texture_ex(
+            texture,
+            self.position.x - dest_size.x * 0.5,
+            self.position.y - dest_size.y * 0.5,
+            config().wall_color,
+            DrawTextureParams {
+                dest_size: Some(dest_size),
+                rotation: if vertical { f32::to_radians(90.) } else { 0. },
+                ..Default::default()
+            },
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use quad_url::get_program_parameters;
+use std::f32::consts::{PI, TAU};
 
 pub fn get_command_line_argument(arg: &str) -> String {
     let parameters = get_program_parameters();
@@ -14,3 +15,14 @@ pub fn get_command_line_argument(arg: &str) -> String {
     }
     String::new()
 }
+
+/// Интерполяция угла по кратчайшей дуге.
+pub fn lerp_angle(from: f32, to: f32, alpha: f32) -> f32 {
+    let mut da = (to - from) % TAU;
+    if da > PI {
+        da -= TAU;
+    } else if da < -PI {
+        da += TAU;
+    }
+    from + da * alpha
+}
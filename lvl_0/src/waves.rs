@@ -0,0 +1,98 @@
+//! волны enemy_units по описанию `waves` из уровня
+
+use crate::config::config;
+use crate::level::{EnemyUnitDef, WavesDef};
+
+/// Очередь волн: когда и какую волну выпустить.
+pub struct WaveSpawner {
+    spawned: usize,   // сколько волн уже выпущено
+    timer: f32,       // время с начала текущей волны
+    clear_timer: f32, // время, пока поле пустое
+}
+
+impl WaveSpawner {
+    pub fn new() -> Self {
+        Self {
+            spawned: 0,
+            timer: 0.,
+            clear_timer: 0.,
+        }
+    }
+
+    /// Номер текущей волны, 0 - волн еще не было.
+    pub fn wave(&self) -> usize {
+        self.spawned
+    }
+
+    /// Все волны выпущены. Бесконечные волны не заканчиваются.
+    pub fn is_finished(&self, def: &WavesDef) -> bool {
+        def.waves.is_empty()
+            || def.spawn_points.is_empty()
+            || (!def.endless && self.spawned >= def.waves.len())
+    }
+
+    /// Продвинуть таймеры волн.
+    ///
+    /// ### Аргументы
+    ///
+    /// * `dt` - шаг времени.
+    /// * `def` - описание волн уровня.
+    /// * `enemy_units` - сколько enemy_units сейчас на поле.
+    ///
+    /// ### Возвращаемое значение
+    ///
+    /// enemy_units новой волны, если она начинается на этом шаге.
+    pub fn update(
+        &mut self,
+        dt: f32,
+        def: &WavesDef,
+        enemy_units: usize,
+    ) -> Option<Vec<EnemyUnitDef>> {
+        if self.is_finished(def) {
+            return None;
+        }
+        self.timer += dt;
+        if enemy_units == 0 {
+            self.clear_timer += dt;
+        } else {
+            self.clear_timer = 0.;
+        }
+
+        let timed_out = match self.spawned.checked_sub(1) {
+            Some(last) => def.waves[last % def.waves.len()]
+                .duration
+                .is_some_and(|duration| self.timer >= duration),
+            None => false,
+        };
+        if self.clear_timer < def.delay.unwrap_or(config().wave_delay) && !timed_out {
+            return None;
+        }
+
+        let wave = &def.waves[self.spawned % def.waves.len()];
+        self.spawned += 1;
+        self.timer = 0.;
+        self.clear_timer = 0.;
+
+        let mut units = vec![];
+        let mut point = 0;
+        for group in wave.units.iter() {
+            for _ in 0..group.count {
+                let index = group.spawn_point.unwrap_or_else(|| {
+                    point += 1;
+                    point - 1
+                });
+                let position = def.spawn_points[index % def.spawn_points.len()];
+                units.push(EnemyUnitDef {
+                    position,
+                    hit_points: group.hit_points,
+                    speed: group.speed,
+                    rotation_speed: group.rotation_speed,
+                    unit_type: group.unit_type.clone(),
+                    patrol: vec![],
+                    spawn_spread: config().enemy_unit_spawn_spread,
+                });
+            }
+        }
+        Some(units)
+    }
+}
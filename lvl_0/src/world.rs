@@ -0,0 +1,721 @@
+//! симуляция уровня без окна, ввода и звука
+
+use crate::command::Command;
+use crate::config::config;
+use crate::level::{EnemyUnitDef, Level};
+use crate::navigation::FlowField;
+use crate::objectives::{LevelResult, ObjectiveContext, Objectives};
+use crate::units::enemy_unit::EnemyUnit;
+use crate::units::main_unit::MainUnit;
+use crate::units::projectile::{Projectile, Team};
+use crate::units::target_unit::TargetUnit;
+use crate::units::wall::Wall;
+use crate::waves::WaveSpawner;
+use macroquad::prelude::{info, Vec2};
+use macroquad::rand::RandGenerator;
+
+/// Событие шага симуляции, на которое реагирует отрисовка и звук.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    /// `main_unit` выстрелил
+    MainUnitShoot { position: Vec2 },
+    /// enemy_unit выстрелил
+    EnemyUnitShoot { position: Vec2 },
+    /// попадание в `target_unit`
+    TargetUnitHit { position: Vec2, alive: bool },
+    /// попадание в `enemy_unit`
+    EnemyUnitHit { position: Vec2, killed: bool },
+    /// `main_unit` получил урон
+    MainUnitHit {
+        position: Vec2,
+        hit_points: f32,
+        damage: f32,
+    },
+    /// уровень провален: цель провалена или `main_unit` уничтожен
+    GameOver,
+    /// снаряд остановлен стеной
+    WallHit { position: Vec2 },
+    /// началась волна enemy_units, `wave` с единицы
+    WaveStart { wave: usize },
+    /// все цели выполнены, уровень пройден
+    LevelComplete,
+}
+
+/// Состояние уровня.
+/// Вся случайность берется из `rng`, поэтому одно и то же зерно
+/// и одна и та же последовательность `Command` дают один и тот же результат.
+pub struct World {
+    pub(crate) main_unit: MainUnit,
+    pub(crate) target_unit: TargetUnit,
+    pub(crate) enemy_units: Vec<EnemyUnit>,
+    pub(crate) projectiles: Vec<Projectile>,
+    pub(crate) walls: Vec<Wall>,
+    pub(crate) navigation: FlowField,
+    pub(crate) size: Vec2,
+    /// видимая часть поля, не больше `size`
+    pub(crate) view: Vec2,
+    pub(crate) waves: WaveSpawner,
+    pub(crate) objectives: Objectives,
+    time: f32,                   // время попытки
+    enemy_contact: bool,         // `main_unit` касается enemy_unit на этом шаге
+    result: Option<LevelResult>, // итог, после него шаги ничего не меняют
+    level: Level,
+    seed: u64,
+    rng: RandGenerator,
+    events: Vec<Event>,
+}
+
+impl World {
+    /// Создать уровень `level` со случайностью из зерна `seed`.
+    pub fn new(level: Level, seed: u64) -> Self {
+        let main_unit = spawn_main_unit(&level);
+        let def = &level.target_unit;
+        let target_unit = TargetUnit::new(def.position.into(), def.hit_points);
+        let walls: Vec<Wall> = level
+            .walls
+            .iter()
+            .map(|def| Wall::new(def.position.into(), def.size.into(), def.kind))
+            .collect();
+        let navigation = FlowField::new(
+            level.size.into(),
+            config().navigation_cell_size,
+            &walls,
+            config().enemy_unit_radius,
+        );
+
+        let size: Vec2 = level.size.into();
+        let view = level.view.map_or(size, |view| Vec2::from(view).min(size));
+
+        let mut world = Self {
+            main_unit,
+            target_unit,
+            enemy_units: Vec::new(),
+            projectiles: vec![],
+            walls,
+            navigation,
+            size,
+            view,
+            waves: WaveSpawner::new(),
+            objectives: Objectives::new(&level.objectives),
+            time: 0.,
+            enemy_contact: false,
+            result: None,
+            level,
+            seed,
+            rng: RandGenerator::new(),
+            events: vec![],
+        };
+        world.rng.srand(seed);
+        world.start();
+        world
+    }
+
+    /// запустить игру.
+    fn start(&mut self) {
+        // спавн `enemy_units`
+        for def in self.level.enemy_units.clone().iter() {
+            self.spawn_single_enemy_unit(def);
+        }
+    }
+
+    /// перезапустить игру
+    /// здоровье юнитов и позиции будут восстановленны
+    pub fn restart(&mut self) {
+        // очистить поле
+        self.enemy_units = vec![];
+        self.projectiles = vec![];
+        self.waves = WaveSpawner::new();
+        self.objectives = Objectives::new(&self.level.objectives);
+        self.time = 0.;
+        self.result = None;
+
+        // восстановить `main_unit`
+        self.main_unit = spawn_main_unit(&self.level);
+
+        // восстановить `target_unit`
+        self.target_unit.hit_points = self.level.target_unit.hit_points;
+        self.target_unit.alive = true;
+
+        // каждая попытка начинается с той же случайности
+        self.rng.srand(self.seed);
+        self.start();
+    }
+
+    /// создать enemy_unit по описанию из уровня
+    fn spawn_single_enemy_unit(&mut self, def: &EnemyUnitDef) {
+        let spread = def.spawn_spread;
+        let x = def.position[0] + self.rng.gen_range(-spread, spread);
+        let y = def.position[1] + self.rng.gen_range(-spread, spread);
+        let behaviour = match &def.unit_type {
+            Some(name) => self
+                .level
+                .enemy_types
+                .get(name)
+                .cloned()
+                .unwrap_or_else(|| {
+                    info!("level: нет типа enemy_unit {}", name);
+                    Default::default()
+                }),
+            None => Default::default(),
+        };
+        let patrol = def.patrol.iter().map(|p| Vec2::from(*p)).collect();
+        let flank_side = if self.rng.gen_range(0, 2) == 0 {
+            -1.
+        } else {
+            1.
+        };
+        let speed = def.speed.unwrap_or(config().enemy_unit_speed);
+        let rotation_speed = def
+            .rotation_speed
+            .unwrap_or(config().enemy_unit_rotation_speed);
+        let unit = EnemyUnit::new(Vec2 { x, y }, def.hit_points, speed, rotation_speed)
+            .with_behaviour(behaviour, patrol, flank_side);
+        self.enemy_units.push(unit);
+    }
+
+    /// Описание уровня.
+    pub fn level(&self) -> &Level {
+        &self.level
+    }
+
+    /// Зерно случайных чисел уровня.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Забрать накопленные события.
+    pub fn drain_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Итог уровня. После итога шаги симуляции ничего не меняют до `restart`.
+    pub fn result(&self) -> Option<LevelResult> {
+        self.result
+    }
+
+    /// Шаг симуляции длиной `dt` с управлением `command`.
+    pub fn step(&mut self, dt: f32, command: &Command) {
+        if self.result.is_some() {
+            return;
+        }
+        self.time += dt;
+        let first_event = self.events.len();
+        self.target_unit.shift = Vec2::new(0., 0.);
+
+        // стрельба и спавн выстрела
+        self.main_unit_shoot(dt, command);
+
+        // удалить дохлые юниты
+        self.remove_dead_enemy_units();
+
+        // следующая волна
+        self.spawn_wave(dt);
+
+        // обновить всех коричневыз
+        self.update_enemy_units(dt);
+
+        // стрельба enemy_units
+        self.enemy_units_shoot(dt);
+
+        // столкновения enemy_units с `main_unit`
+        self.main_unit_contact();
+
+        // Удаление снарядов на отлете
+        self.remove_projectile_out_of_range();
+
+        // поражение главной мишени
+        self.target_unit_hit(dt);
+
+        // снаряды, попавшие в стены
+        self.walls_hit();
+
+        // поражение enemy_units
+        self.enemy_units_hit();
+
+        // поражение `main_unit`
+        self.main_unit_hit();
+
+        // выполнение целей уровня
+        self.update_objectives(first_event);
+    }
+
+    /// выполнение целей уровня по событиям этого шага
+    fn update_objectives(&mut self, first_event: usize) {
+        let events = &self.events[first_event..];
+        let waves_left = match &self.level.waves {
+            Some(def) => !self.waves.is_finished(def),
+            None => false,
+        };
+        let context = ObjectiveContext {
+            time: self.time,
+            target_destroyed: self.target_unit.hit_points
+                <= config().target_unit_destroyed_hit_points,
+            target_hit: events
+                .iter()
+                .any(|e| matches!(e, Event::TargetUnitHit { .. })),
+            enemy_contact: self.enemy_contact,
+            enemies_left: waves_left || self.enemy_units.iter().any(|u| u.hit_points > 0.),
+            main_unit_alive: self.main_unit.alive,
+        };
+        self.result = self.objectives.update(&context);
+        match self.result {
+            Some(LevelResult::Success) => self.events.push(Event::LevelComplete),
+            Some(LevelResult::Failure) => self.events.push(Event::GameOver),
+            None => {}
+        }
+    }
+
+    /// стрельба и спавн выстрела
+    fn main_unit_shoot(&mut self, dt: f32, command: &Command) {
+        let shoot = self.main_unit.update(dt, command, self.size);
+        for wall in self.walls.iter() {
+            wall.push_out(&mut self.main_unit.position, config().main_unit_radius);
+        }
+
+        if shoot {
+            let position = Vec2::new(
+                // точка появления выстрела
+                self.main_unit.position.x
+                    + 65. * (self.main_unit.rotation - f32::to_radians(90.)).cos(),
+                self.main_unit.position.y
+                    + 65. * (self.main_unit.rotation - f32::to_radians(90.)).sin(),
+            );
+
+            let projectile = Projectile::new(
+                self.main_unit.rotation,
+                position,
+                self.main_unit.speed * 3.,
+                self.main_unit.shoot_range,
+                Team::Player,
+            );
+            self.projectiles.push(projectile);
+            self.events.push(Event::MainUnitShoot { position });
+        }
+    }
+
+    /// выпустить волну enemy_units, когда подошло время
+    fn spawn_wave(&mut self, dt: f32) {
+        let Some(def) = &self.level.waves else {
+            return;
+        };
+        if let Some(units) = self.waves.update(dt, def, self.enemy_units.len()) {
+            for def in units.iter() {
+                self.spawn_single_enemy_unit(def);
+            }
+            self.events.push(Event::WaveStart {
+                wave: self.waves.wave(),
+            });
+        }
+    }
+
+    /// Точка, к которой направляется enemy_unit.
+    /// Напрямую к `main_unit`, если стены не мешают, иначе по полю направлений.
+    pub(crate) fn enemy_unit_waypoint(&self, position: Vec2) -> Vec2 {
+        let goal = self.main_unit.position;
+        if self
+            .walls
+            .iter()
+            .all(|w| w.segment_hit(position, goal).is_none())
+        {
+            return goal;
+        }
+        self.navigation.waypoint(position).unwrap_or(goal)
+    }
+
+    /// Обновить все `enemy_units`
+    fn update_enemy_units(&mut self, dt: f32) {
+        self.navigation.update(self.main_unit.position);
+        let player = self.main_unit.position;
+        for i in 0..self.enemy_units.len() {
+            let units = self.enemy_units.clone();
+            self.enemy_units[i].update_state(dt, player);
+            let waypoint = self.enemy_unit_waypoint(self.enemy_units[i].position);
+            // отступление и обход сбоку не уводят за край поля
+            let radius = self.enemy_units[i].radius;
+            let (min, max) = (
+                Vec2::splat(radius),
+                (self.size - radius).max(Vec2::splat(radius)),
+            );
+            let target = self.enemy_units[i]
+                .steering_target(player, waypoint)
+                .map(|target| target.clamp(min, max));
+            self.enemy_units[i].update(dt, target, &units, i);
+
+            let unit = &mut self.enemy_units[i];
+            for wall in self.walls.iter() {
+                if let Some(normal) = wall.push_out(&mut unit.position, unit.radius) {
+                    unit.deflect(normal);
+                }
+            }
+            unit.position = unit.position.clamp(min, max);
+        }
+    }
+
+    /// стрельба enemy_units
+    /// Разброс берется из `rng`; сквозь стены не стреляют.
+    fn enemy_units_shoot(&mut self, dt: f32) {
+        let player = self.main_unit.position;
+        for i in 0..self.enemy_units.len() {
+            let unit = &mut self.enemy_units[i];
+            let position = unit.position;
+            let radius = unit.radius;
+            let Some(weapon) = unit.update_weapon(dt, player).cloned() else {
+                continue;
+            };
+            if self
+                .walls
+                .iter()
+                .any(|w| w.segment_hit(position, player).is_some())
+            {
+                continue;
+            }
+
+            let spread = f32::to_radians(config().enemy_unit_shoot_spread)
+                * (1. - weapon.accuracy().clamp(0., 1.));
+            let error = if spread > 0. {
+                self.rng.gen_range(-spread, spread)
+            } else {
+                0.
+            };
+            let aim = player - position;
+            // у снаряда угол 0 - вверх
+            let rotation = aim.y.atan2(aim.x) + f32::to_radians(90.) + error;
+            let direction = Vec2::new(
+                (rotation - f32::to_radians(90.)).cos(),
+                (rotation - f32::to_radians(90.)).sin(),
+            );
+            let position = position + direction * (radius + 5.);
+            self.projectiles.push(Projectile::new(
+                rotation,
+                position,
+                weapon.projectile_speed(),
+                weapon.range(),
+                Team::Enemy,
+            ));
+            self.events.push(Event::EnemyUnitShoot { position });
+        }
+    }
+
+    /// столкновения enemy_units с `main_unit`
+    fn main_unit_contact(&mut self) {
+        let position = self.main_unit.position;
+        let radius = config().main_unit_radius;
+        let touching = self
+            .enemy_units
+            .iter()
+            .any(|u| u.position.distance(position) < u.radius + radius);
+        self.enemy_contact = touching;
+        if touching {
+            self.damage_main_unit(config().enemy_unit_contact_damage);
+        }
+    }
+
+    /// поражение `main_unit` снарядами enemy_units
+    fn main_unit_hit(&mut self) {
+        let position = self.main_unit.position;
+        let radius = config().main_unit_radius;
+        let mut hits = 0;
+        for p in self.projectiles.iter_mut() {
+            if p.alive && p.team == Team::Enemy && p.position.distance(position) < radius {
+                p.alive = false;
+                hits += 1;
+            }
+        }
+        for _ in 0..hits {
+            self.damage_main_unit(config().enemy_unit_projectile_damage);
+        }
+    }
+
+    /// урон `main_unit`
+    fn damage_main_unit(&mut self, damage: f32) {
+        if !self.main_unit.hit(damage) {
+            return;
+        }
+        self.events.push(Event::MainUnitHit {
+            position: self.main_unit.position,
+            hit_points: self.main_unit.hit_points,
+            damage,
+        });
+        info!("main_unit.hit_points: {:?}", self.main_unit.hit_points);
+    }
+
+    /// удалить дохлые юниты
+    fn remove_dead_enemy_units(&mut self) {
+        self.enemy_units.retain(|u| u.hit_points > 0.);
+    }
+
+    /// Удаление снарядов на отлете
+    fn remove_projectile_out_of_range(&mut self) {
+        self.projectiles.retain(|p| {
+            ((p.start_position.x - p.position.x).powf(2f32)
+                + (p.start_position.y - p.position.y).powf(2f32)
+                < p.range.powf(2f32))
+                && p.alive
+        });
+    }
+
+    /// поражение главной мишени
+    fn target_unit_hit(&mut self, dt: f32) {
+        for i in 0..self.projectiles.len() {
+            let p = &mut self.projectiles[i];
+
+            if p.team == Team::Player
+                && (p.position.x - self.target_unit.position.x).powf(2f32)
+                    + (p.position.y - self.target_unit.position.y).powf(2f32)
+                    < self.target_unit.radius.powf(2f32)
+            {
+                p.alive = false;
+                self.target_unit.update(true, -20., p.rotation);
+                self.events.push(Event::TargetUnitHit {
+                    position: p.position,
+                    alive: self.target_unit.alive,
+                });
+                info!("target_unit.hit_points: {:?}", self.target_unit.hit_points);
+            } else {
+                p.update(dt, self.size);
+            }
+        }
+    }
+
+    /// снаряды, попавшие в стены
+    fn walls_hit(&mut self) {
+        for p in self.projectiles.iter_mut() {
+            if !p.alive {
+                continue;
+            }
+            // по отрезку пути за шаг, чтобы снаряд не проскочил тонкую стену
+            let hit = self
+                .walls
+                .iter()
+                .find_map(|w| w.segment_hit(p.previous_position, p.position));
+            if let Some(position) = hit {
+                p.alive = false;
+                p.position = position;
+                self.events.push(Event::WallHit { position });
+            }
+        }
+    }
+
+    /// поражение enemy_units
+    fn enemy_units_hit(&mut self) {
+        for i in 0..self.projectiles.len() {
+            let p = &mut self.projectiles[i];
+            if !p.alive || p.team != Team::Player {
+                continue;
+            }
+            for j in 0..self.enemy_units.len() {
+                let u = &mut self.enemy_units[j];
+                if u.hit_points <= 0. {
+                    continue;
+                }
+                let dx = p.position.x - u.position.x;
+                let dy = p.position.y - u.position.y;
+                let dist = (dx.powf(2.) + dy.powf(2.)).sqrt();
+                if dist < u.radius {
+                    u.hit_points -= 20.;
+                    self.events.push(Event::EnemyUnitHit {
+                        position: p.position,
+                        killed: u.hit_points <= 0.,
+                    });
+
+                    let da = u.rotation - p.rotation;
+                    p.alive = false;
+                    u.rotation += da.signum() * f32::to_radians(20.);
+                    u.stun();
+                    // снаряд поражает только одного enemy_unit
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// `main_unit` в начальном состоянии из описания уровня
+/// Незаданные параметры берутся из текущих настроек.
+fn spawn_main_unit(level: &Level) -> MainUnit {
+    let def = &level.main_unit;
+    let config = config();
+    MainUnit::new(
+        def.position.into(),
+        def.speed.unwrap_or(config.main_unit_speed),
+        def.shoot_delay.unwrap_or(config.main_unit_shoot_delay),
+        def.shoot_range.unwrap_or(config.main_unit_shoot_range),
+        def.hit_points.unwrap_or(config.main_unit_hit_points),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::FIXED_DT;
+    use crate::units::enemy_unit::EnemyState;
+
+    /// Поле 800x800: `main_unit` снизу, `target_unit` сверху.
+    fn level(extra: &str) -> Level {
+        Level::parse(&format!(
+            r#"{{
+                "name": "test",
+                "size": [800, 800],
+                "main_unit": {{ "position": [400, 640] }},
+                "target_unit": {{ "position": [400, 160] }}
+                {}
+            }}"#,
+            extra
+        ))
+        .unwrap()
+    }
+
+    fn run(world: &mut World, command: &Command, steps: usize) -> Vec<Event> {
+        let mut events = vec![];
+        for _ in 0..steps {
+            world.step(FIXED_DT, command);
+            events.extend(world.drain_events());
+        }
+        events
+    }
+
+    fn shoot_at(point: Vec2) -> Command {
+        Command {
+            shoot: true,
+            target_point: point,
+            ..Command::new()
+        }
+    }
+
+    #[test]
+    fn projectile_hits_target() {
+        let mut world = World::new(level(""), 0);
+        let events = run(&mut world, &shoot_at(Vec2::new(400., 160.)), 120);
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, Event::TargetUnitHit { .. })));
+        assert!(world.target_unit.hit_points < world.target_unit.max_hit_points);
+    }
+
+    #[test]
+    fn wall_pushes_main_unit_out() {
+        let mut world = World::new(
+            level(r#", "walls": [{ "position": [400, 420], "size": [300, 20] }]"#),
+            0,
+        );
+        let command = Command {
+            wasd: Vec2::new(0., -1.),
+            ..Command::new()
+        };
+        run(&mut world, &command, 300);
+        let position = world.main_unit.position;
+        assert!(
+            position.y >= 430. + config().main_unit_radius - 0.5,
+            "{position}"
+        );
+        assert!((position.x - 400.).abs() < 1., "{position}");
+    }
+
+    #[test]
+    fn destroyed_target_completes_level() {
+        let mut world = World::new(level(""), 0);
+        let events = run(&mut world, &shoot_at(Vec2::new(400., 160.)), 1200);
+        assert_eq!(world.result(), Some(LevelResult::Success));
+        assert!(events.contains(&Event::LevelComplete));
+        assert!(!events.contains(&Event::GameOver));
+    }
+
+    #[test]
+    fn enemy_contact_fails_level() {
+        let mut world = World::new(level(r#", "enemy_units": [{ "position": [400, 560] }]"#), 0);
+        world.main_unit.hit_points = config().enemy_unit_contact_damage;
+        let events = run(&mut world, &Command::new(), 600);
+        assert_eq!(world.result(), Some(LevelResult::Failure));
+        assert!(events.contains(&Event::GameOver));
+        // после итога шаги ничего не меняют
+        assert!(run(&mut world, &Command::new(), 10).is_empty());
+    }
+
+    /// Состояние, по которому сравниваются прогоны
+    fn snapshot(world: &World) -> (Vec<(Vec2, f32)>, f32, f32, Option<LevelResult>) {
+        (
+            world
+                .enemy_units
+                .iter()
+                .map(|u| (u.position, u.hit_points))
+                .collect(),
+            world.main_unit.hit_points,
+            world.target_unit.hit_points,
+            world.result(),
+        )
+    }
+
+    #[test]
+    fn same_seed_and_commands_give_same_run() {
+        let replay = |seed| {
+            let level = Level::parse(include_str!("../../assets/levels/lvl_1.json")).unwrap();
+            let mut world = World::new(level, seed);
+            let mut snapshots = vec![];
+            for i in 0..1200 {
+                let angle = i as f32 * 0.01;
+                let command = Command {
+                    shoot: i % 3 == 0,
+                    wasd: Vec2::new(angle.cos(), angle.sin()),
+                    target_point: Vec2::new(200. + 300. * angle.sin(), 140.),
+                    ..Command::new()
+                };
+                world.step(FIXED_DT, &command);
+                world.drain_events();
+                if i % 60 == 0 {
+                    snapshots.push(snapshot(&world));
+                }
+            }
+            snapshots
+        };
+        assert_eq!(replay(7), replay(7));
+    }
+
+    #[test]
+    fn projectile_hits_one_of_overlapping_enemies() {
+        let mut world = World::new(level(""), 0);
+        let position = Vec2::new(200., 400.);
+        for _ in 0..2 {
+            world
+                .enemy_units
+                .push(EnemyUnit::new(position, 100., 0., 0.));
+        }
+        // снаряд летит в ту же сторону, куда повернуты enemy_units
+        let rotation = world.enemy_units[0].rotation;
+        world
+            .projectiles
+            .push(Projectile::new(rotation, position, 0., 100., Team::Player));
+        world.enemy_units_hit();
+
+        let hits = world
+            .drain_events()
+            .into_iter()
+            .filter(|e| matches!(e, Event::EnemyUnitHit { .. }))
+            .count();
+        assert_eq!(hits, 1);
+        let damaged = world
+            .enemy_units
+            .iter()
+            .filter(|u| u.hit_points < 100.)
+            .count();
+        assert_eq!(damaged, 1);
+        assert!(world.enemy_units.iter().all(|u| u.rotation.is_finite()));
+    }
+
+    #[test]
+    fn retreating_enemy_stays_on_field() {
+        // здоровье всегда ниже `retreat_hit_points`: юнит только отступает
+        let mut world = World::new(
+            level(
+                r#", "enemy_types": { "coward": { "retreat_hit_points": 200 } },
+                "enemy_units": [{ "position": [400, 720], "type": "coward" }]"#,
+            ),
+            0,
+        );
+        run(&mut world, &Command::new(), 600);
+
+        let unit = &world.enemy_units[0];
+        assert_eq!(unit.state, EnemyState::Retreat);
+        assert!(unit.position.cmpge(Vec2::ZERO).all(), "{}", unit.position);
+        assert!(unit.position.cmple(world.size).all(), "{}", unit.position);
+    }
+}